@@ -0,0 +1,2461 @@
+//! We want to make sure that the struct that our caller passes us is in the
+//! right form. However, we don't want to clutter up our code generation
+//! logic with lots of error handling. So instead, we take in our `DeriveInput`
+//! and do all the error handling in one place, transforming it into an AST
+//! specific to our crate if it's valid.
+
+use syn;
+
+use diagnostics::Warning;
+use options::{parse_field_options, parse_options, FieldOptions, Options};
+use type_name;
+
+/// Fields along with their `#[shrinkwrap(..)]` options.
+type Fields = Vec<(syn::Field, FieldOptions)>;
+/// A field along with where it is in its struct.
+type IndexedField = (usize, (syn::Field, FieldOptions));
+
+/// Whether generated code can use things that need an allocator.
+pub const HAS_ALLOC: bool = cfg!(any(feature = "std", feature = "alloc"));
+
+bitflags! {
+  /// Controls which code and implementations we generate.
+  pub struct ShrinkwrapFlags: u64 {
+    const SW_MUT         = 0b000000000000000000000001;
+    const SW_IGNORE_VIS  = 0b000000000000000000000010;
+    const SW_TRANSPARENT = 0b000000000000000000000100;
+    const SW_COW         = 0b000000000000000000001000;
+    const SW_CTOR_INTO   = 0b000000000000000000010000;
+    const SW_CONST_FNS   = 0b000000000000000000100000;
+    const SW_COPY_GET    = 0b000000000000000001000000;
+    const SW_WITH_FIELDS = 0b000000000000000010000000;
+    const SW_MUST_USE    = 0b000000000000000100000000;
+    const SW_AS_REF_ALL  = 0b000000000000001000000000;
+    const SW_DEREF_VIA   = 0b000000000000010000000000;
+    const SW_TRANSITIVE  = 0b000000000000100000000000;
+    const SW_PATH_LIKE   = 0b000000000001000000000000;
+    const SW_BYTES       = 0b000000000010000000000000;
+    const SW_FWD_AS_REF  = 0b000000000100000000000000;
+    const SW_FWD_BORROW  = 0b000000001000000000000000;
+    const SW_EQUIVALENT  = 0b000000010000000000000000;
+    const SW_IGNORE_BORROW = 0b000000100000000000000000;
+    const SW_ZERO_COST   = 0b000001000000000000000000;
+    const SW_SEND_SYNC   = 0b000010000000000000000000;
+    const SW_GEN_TESTS   = 0b000100000000000000000000;
+    const SW_UNSIZED     = 0b001000000000000000000000;
+    const SW_AS_REF_VIA  = 0b010000000000000000000000;
+    const SW_OPTION      = 0b100000000000000000000000;
+    const SW_SLICE       = 0b1000000000000000000000000;
+    const SW_LOCK        = 0b10000000000000000000000000;
+    const SW_CELL        = 0b100000000000000000000000000;
+    const SW_ATOMIC      = 0b1000000000000000000000000000;
+    const SW_UUID        = 0b10000000000000000000000000000;
+    const SW_MEM_HELPERS = 0b100000000000000000000000000000;
+    const SW_ACCESSORS   = 0b1000000000000000000000000000000;
+    const SW_OPT_RESULT  = 0b10000000000000000000000000000000;
+    const SW_ITER        = 0b100000000000000000000000000000000;
+    const SW_NO_MUT      = 0b1000000000000000000000000000000000;
+    const SW_CHECKED     = 0b10000000000000000000000000000000000;
+  }
+}
+
+bitflags! {
+  /// Which of the standard library's traits we implement; everything by
+  /// default, or just the ones listed in `#[shrinkwrap(traits(..))]`, minus
+  /// any opted out of with `no_deref` and friends.
+  pub struct Traits: u32 {
+    const DEREF      = 0b000001;
+    const BORROW     = 0b000010;
+    const AS_REF     = 0b000100;
+    const DEREF_MUT  = 0b001000;
+    const BORROW_MUT = 0b010000;
+    const AS_MUT     = 0b100000;
+  }
+}
+
+/// The names of the inherent methods we generate that can be renamed with
+/// `#[shrinkwrap(rename(..))]`.
+pub struct MethodNames {
+  pub into_inner: syn::Ident,
+  pub as_inner: syn::Ident,
+  pub as_inner_mut: syn::Ident,
+  pub inner_mut: syn::Ident,
+  pub get: syn::Ident,
+  pub new: syn::Ident,
+  pub set: syn::Ident,
+  pub replace: syn::Ident,
+  pub take: syn::Ident,
+  pub swap: syn::Ident,
+  pub map: syn::Ident,
+  pub map_ref: syn::Ident,
+  pub map_mut: syn::Ident,
+  pub transform: syn::Ident,
+  pub transform_ref: syn::Ident,
+  pub into_parts: syn::Ident,
+  pub wrap_opt: syn::Ident,
+  pub wrap_result: syn::Ident,
+  pub unwrap_opt: syn::Ident,
+  pub unwrap_result: syn::Ident,
+  pub wrap_iter: syn::Ident,
+  pub unwrap_iter: syn::Ident,
+  pub update: syn::Ident,
+  pub borrow: syn::Ident,
+  pub try_borrow: syn::Ident,
+  pub borrow_mut: syn::Ident,
+}
+
+impl MethodNames {
+  pub fn new() -> Self {
+    let name = |name| syn::Ident::new(name, proc_macro2::Span::call_site());
+
+    MethodNames {
+      into_inner: name("into_inner"),
+      as_inner: name("as_inner"),
+      as_inner_mut: name("as_inner_mut"),
+      inner_mut: name("inner_mut"),
+      get: name("get"),
+      new: name("new"),
+      set: name("set"),
+      replace: name("replace"),
+      take: name("take"),
+      swap: name("swap"),
+      map: name("map"),
+      map_ref: name("map_ref"),
+      map_mut: name("map_mut"),
+      transform: name("transform"),
+      transform_ref: name("transform_ref"),
+      into_parts: name("into_parts"),
+      wrap_opt: name("wrap_opt"),
+      wrap_result: name("wrap_result"),
+      unwrap_opt: name("unwrap_opt"),
+      unwrap_result: name("unwrap_result"),
+      wrap_iter: name("wrap_iter"),
+      unwrap_iter: name("unwrap_iter"),
+      update: name("update"),
+      borrow: name("borrow"),
+      try_borrow: name("try_borrow"),
+      borrow_mut: name("borrow_mut"),
+    }
+  }
+
+  pub fn by_name(&mut self, method: &syn::Path) -> Option<&mut syn::Ident> {
+    let method = method.get_ident()?.to_string();
+
+    match method.as_str() {
+      "into_inner" => Some(&mut self.into_inner),
+      "as_inner" => Some(&mut self.as_inner),
+      "as_inner_mut" => Some(&mut self.as_inner_mut),
+      "inner_mut" => Some(&mut self.inner_mut),
+      "get" => Some(&mut self.get),
+      "new" => Some(&mut self.new),
+      "set" => Some(&mut self.set),
+      "replace" => Some(&mut self.replace),
+      "take" => Some(&mut self.take),
+      "swap" => Some(&mut self.swap),
+      "map" => Some(&mut self.map),
+      "map_ref" => Some(&mut self.map_ref),
+      "map_mut" => Some(&mut self.map_mut),
+      "transform" => Some(&mut self.transform),
+      "transform_ref" => Some(&mut self.transform_ref),
+      "into_parts" => Some(&mut self.into_parts),
+      "wrap_opt" => Some(&mut self.wrap_opt),
+      "wrap_result" => Some(&mut self.wrap_result),
+      "unwrap_opt" => Some(&mut self.unwrap_opt),
+      "unwrap_result" => Some(&mut self.unwrap_result),
+      "wrap_iter" => Some(&mut self.wrap_iter),
+      "unwrap_iter" => Some(&mut self.unwrap_iter),
+      "update" => Some(&mut self.update),
+      "borrow" => Some(&mut self.borrow),
+      "try_borrow" => Some(&mut self.try_borrow),
+      "borrow_mut" => Some(&mut self.borrow_mut),
+      _ => None,
+    }
+  }
+}
+
+/// The main field, when it's picked on the struct itself with
+/// `#[shrinkwrap(main_field = "addr")]` or `#[shrinkwrap(main_field = 2)]`
+/// rather than by marking the field.
+pub enum MainField {
+  Named(String),
+  Index(usize),
+}
+
+/// For `#[shrinkwrap(main_field = "config.addr", main_type = "String")]`, the
+/// rest of the path into the main field, along with the type at the end of
+/// it, which we have no way of looking up ourselves.
+pub struct NestedField {
+  pub path: Vec<syn::Member>,
+  pub ty: syn::Type,
+}
+
+/// From `#[shrinkwrap(cfg(feature = "std", traits(Borrow)))]`: impls of the
+/// given traits only exist when the predicate holds.
+pub struct CfgGate {
+  pub traits: Traits,
+  pub predicate: syn::NestedMeta,
+}
+
+/// What to mark the accessors in generated trait impls with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inline {
+  /// `#[inline]`, the default.
+  Hint,
+  /// `#[inline(always)]`, from `#[shrinkwrap(inline(always))]`.
+  Always,
+  /// Nothing, from `#[shrinkwrap(no_inline)]`.
+  Omit,
+}
+
+/// What to do about mutable traits when we can't tell whether the inner field
+/// is as visible as the struct, from `#[shrinkwrap(visibility_policy = "..")]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisibilityPolicy {
+  /// Refuse to derive them, the default.
+  Error,
+  /// Derive them anyway, but warn; the default with `nightly-diagnostics`.
+  Warn,
+  /// Derive them anyway.
+  Allow,
+}
+
+/// How `Display` and `FromStr` spell out the bytes of an ID, from
+/// `#[shrinkwrap(display_encoding = "..")]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  Hex,
+  Base58,
+  Base64,
+}
+
+/// An extension trait for the inner type, for `#[shrinkwrap(ext_trait)]`.
+pub struct ExtTrait {
+  pub name: syn::Ident,
+  pub method: syn::Ident,
+}
+
+/// Functions of the inner type to forward to, from
+/// `#[shrinkwrap(delegate_constructors(..))]` and
+/// `#[shrinkwrap(delegate_owned(..))]`. Procedural macros can't look up the
+/// inner type's signatures, so these are spelled out by the user.
+pub struct Delegates {
+  pub constructors: Vec<syn::Signature>,
+  pub owned: Vec<syn::Signature>,
+}
+
+pub struct StructDetails {
+  pub flags: ShrinkwrapFlags,
+  pub traits: Traits,
+  pub names: MethodNames,
+  pub ext_trait: Option<ExtTrait>,
+  pub delegates: Delegates,
+  /// If we should generate a `new()` constructor, what visibility it has.
+  pub constructor: Option<syn::Visibility>,
+  /// With `#[shrinkwrap(mut_access = "..")]`, mutable access goes through an
+  /// inherent `inner_mut()` with this visibility instead of trait impls.
+  pub mut_access: Option<syn::Visibility>,
+  /// What `Deref` should point at, if not the inner type itself. The inner
+  /// type has to deref-coerce into it.
+  pub deref_target: Option<syn::Type>,
+  /// With `#[shrinkwrap(owned = "..")]`, this is the borrowed half of a pair
+  /// like `Path` and `PathBuf`, and the main field is unsized.
+  pub owned: Option<syn::Type>,
+  /// With `#[shrinkwrap(borrowed = "..")]`, this is the owned half.
+  pub borrowed: Option<syn::Type>,
+  /// Whether the main field is unsized, like in the borrowed half of a pair,
+  /// in which case it can only be handed out by reference.
+  pub unsized_inner: bool,
+  pub cfgs: Vec<CfgGate>,
+  /// Where generated code should find the standard library, if not `::std`
+  /// or `::core`.
+  pub std_path: Option<syn::Path>,
+  pub inline: Inline,
+  pub visibility_policy: VisibilityPolicy,
+  /// With `#[shrinkwrap(gen_tests = "..")]`, the inner value the generated
+  /// tests wrap.
+  pub test_sample: Option<syn::Expr>,
+  pub display_encoding: Option<Encoding>,
+  pub lock_backend: LockBackend,
+  /// Soft problems, to mention if we can without failing the build.
+  pub warnings: Vec<Warning>,
+  pub ident: syn::Ident,
+  pub generics: syn::Generics,
+  pub visibility: syn::Visibility,
+}
+
+/// Represents either a tuple or bracketed struct with at least one field.
+pub struct Struct {
+  pub inner_field: proc_macro2::TokenStream,
+  pub inner_type: syn::Type,
+  pub inner_visibility: syn::Visibility,
+  /// Where the main field (or the field it's nested in) was written, for
+  /// the generated code that stands in for it.
+  pub inner_span: proc_macro2::Span,
+  /// Where the main field is among all of the struct's fields.
+  pub inner_index: usize,
+  /// All the fields that *aren't* the main field, in declaration order.
+  pub other_fields: Vec<OtherField>,
+  /// Where the mutable traits and accessors point; usually the main field,
+  /// unless another field is marked `#[shrinkwrap(main_field_mut)]`.
+  pub inner_field_mut: proc_macro2::TokenStream,
+  pub inner_visibility_mut: syn::Visibility,
+  pub inner_span_mut: proc_macro2::Span,
+  /// The main field's own `///` docs, for the accessors that stand in for it.
+  /// Empty when it's nested, since the docs would be for the outer field.
+  pub inner_docs: Vec<syn::Attribute>,
+  /// Whether the main field is nested inside one of the struct's fields,
+  /// in which case that field counts as one of the other fields, and we
+  /// can't build the struct out of just the inner value.
+  pub nested: bool,
+}
+
+/// A field that isn't the main field. We still need to know how to refer to
+/// these when constructing or destructuring the struct.
+pub struct OtherField {
+  pub member: proc_macro2::TokenStream,
+  pub field: syn::Field,
+  pub options: FieldOptions,
+}
+
+pub fn validate_derive_input(input: syn::DeriveInput) -> syn::Result<(StructDetails, Struct)> {
+  // Errors are spanned at whatever they're about, so that rustc points at
+  // the offending struct, field or option instead of the whole derive.
+
+  use syn::Data::{Enum, Struct, Union};
+  use syn::Fields::{Named, Unnamed};
+  use syn::{DataStruct, DeriveInput, FieldsNamed, FieldsUnnamed};
+
+  let DeriveInput {
+    attrs,
+    vis,
+    ident,
+    generics,
+    data,
+    ..
+  } = input;
+
+  let Options {
+    flags,
+    traits,
+    names,
+    ext_trait,
+    delegates,
+    constructor,
+    mut_access,
+    main_field,
+    main_path,
+    main_type,
+    deref_target,
+    owned,
+    borrowed,
+    cfgs,
+    std_path,
+    inline,
+    visibility_policy,
+    test_sample,
+    display_encoding,
+    lock_backend,
+  } = parse_options(&ident, &attrs)?;
+  let nested = match (main_path.is_empty(), main_type) {
+    (true, None) => None,
+    (false, Some(ty)) => Some(NestedField { path: main_path, ty }),
+    (false, None) => {
+      return Err(syn::Error::new_spanned(
+        &ident,
+        "shrinkwraprs: main_field can only reach into another struct
+if you also give the type at the end of the path, like
+#[shrinkwrap(main_field = \"config.addr\", main_type = \"String\")]",
+      ))
+    }
+    (true, Some(ty)) => {
+      return Err(syn::Error::new_spanned(
+        ty,
+        "shrinkwraprs: main_type is only needed alongside a nested
+main_field, like #[shrinkwrap(main_field = \"config.addr\")]",
+      ))
+    }
+  };
+  let mut details = StructDetails {
+    flags,
+    traits,
+    names,
+    ext_trait,
+    delegates,
+    constructor,
+    mut_access,
+    deref_target,
+    owned,
+    borrowed,
+    cfgs,
+    std_path,
+    inline,
+    visibility_policy,
+    test_sample,
+    display_encoding,
+    lock_backend,
+    unsized_inner: false,
+    warnings: vec![],
+    ident,
+    visibility: vis,
+    generics,
+  };
+
+  let input = match data {
+    Struct(DataStruct {
+      fields: Unnamed(FieldsUnnamed {
+        unnamed: fields, ..
+      }),
+      ..
+    }) => {
+      let fields = with_options(fields)?;
+      validate_tuple(&details, fields, &main_field, nested)?
+    }
+    Struct(DataStruct {
+      fields: Named(FieldsNamed { named: fields, .. }),
+      ..
+    }) => {
+      let fields = with_options(fields)?;
+      validate_nontuple(&details, fields, &main_field, nested)?
+    }
+    Struct(..) => {
+      return Err(syn::Error::new_spanned(
+        &details.ident,
+        "shrinkwraprs needs a struct with at least one field!",
+      ))
+    }
+    Enum(..) => {
+      let ident = &details.ident;
+
+      return Err(syn::Error::new_spanned(
+        ident,
+        format!(
+          "shrinkwraprs does not support enums, and `{0}` is an enum.
+Each variant holds something different (or nothing at
+all), so there's no single field for the wrapper to
+convert to. If you want to wrap the whole enum, derive
+Shrinkwrap on a struct around it instead, like
+`struct Wrapped{0}({0});`.",
+          ident
+        ),
+      ));
+    }
+    Union(..) => {
+      let ident = &details.ident;
+
+      return Err(syn::Error::new_spanned(
+        ident,
+        format!(
+          "shrinkwraprs does not support C-style unions, and `{0}` is a
+union. Only one of its fields is valid at a time, and
+there's no way to tell which without unsafe code, so
+there's no field for the wrapper to convert to. If you
+want to wrap the whole union, derive Shrinkwrap on a
+struct around it instead, like `struct Wrapped{0}({0});`.",
+          ident
+        ),
+      ));
+    }
+  };
+
+  details.unsized_inner = details.owned.is_some()
+    || details.flags.contains(ShrinkwrapFlags::SW_UNSIZED)
+    || is_unsized(&input.inner_type);
+
+  validate_not_packed(&attrs)?;
+  if details.flags.contains(ShrinkwrapFlags::SW_TRANSPARENT) {
+    validate_transparent(&attrs, &details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_CONST_FNS) {
+    validate_const_fns(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_ZERO_COST) {
+    validate_zero_cost(&details)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_GEN_TESTS) {
+    validate_gen_tests(&details, &input)?;
+  }
+  if input.nested && details.constructor.is_some() {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: can't generate a constructor when main_field
+reaches into another struct, since there's no way to fill
+in the rest of that struct.",
+    ));
+  }
+  if details.ext_trait.is_some() {
+    validate_ext_trait(&input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_NO_MUT) {
+    validate_no_mut(&mut details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_CHECKED) {
+    validate_checked(&details)?;
+  }
+  if details.mut_access.is_some() && details.flags.contains(ShrinkwrapFlags::SW_MUT) {
+    return Err(syn::Error::new_spanned(
+      &details.mut_access,
+      "shrinkwraprs: #[shrinkwrap(mut_access = \"..\")] is instead of the
+mutable traits, so it can't be combined with
+#[shrinkwrap(mutable)].",
+    ));
+  }
+  if !details.delegates.constructors.is_empty() {
+    validate_delegate_constructors(&details, &input)?;
+  }
+  if !details.delegates.owned.is_empty() {
+    validate_delegate_owned(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_WITH_FIELDS) {
+    validate_with_fields(&input)?;
+  }
+  validate_borrow_targets(&input)?;
+  if details.flags.contains(ShrinkwrapFlags::SW_DEREF_VIA) && details.deref_target.is_some() {
+    return Err(syn::Error::new_spanned(
+      &details.deref_target,
+      "shrinkwraprs: #[shrinkwrap(deref_via_inner)] already picks the
+deref target, so it can't be combined with deref_target.",
+    ));
+  }
+  // The inner type's deref target could well be the value inside it, if it's
+  // a shrinkwrap too.
+  if details.flags.contains(ShrinkwrapFlags::SW_AS_REF_VIA | ShrinkwrapFlags::SW_TRANSITIVE) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(as_ref_via_inner)] and
+#[shrinkwrap(transitive)] could both implement the same
+`AsRef`, so they can't be combined.",
+    ));
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_OPTION) && option_inner(&input.inner_type).is_none() {
+    return Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(option)] needs the main field to be
+an `Option<T>`.",
+    ));
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_SLICE) {
+    validate_slice(&mut details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_LOCK) {
+    validate_lock(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_CELL) {
+    validate_cell(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_ATOMIC) && atomic_value(&input.inner_type).is_none() {
+    return Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(atomic)] needs the main field to be
+one of the atomic integers or `AtomicBool`, like `AtomicU64`.",
+    ));
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_COW) && !HAS_ALLOC {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(cow)] needs the `std` or `alloc` feature to be enabled",
+    ));
+  }
+  if details.owned.is_some() {
+    validate_owned(&attrs, &details, &input)?;
+  } else if details.unsized_inner {
+    validate_unsized(&attrs, &details, &input)?;
+  }
+  if details.borrowed.is_some() {
+    validate_borrowed(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_FWD_AS_REF) {
+    validate_forward_as_ref(&details)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_FWD_BORROW) {
+    validate_forward_borrow(&details, &input)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_EQUIVALENT) && !cfg!(feature = "equivalent") {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(equivalent)] needs the `equivalent` feature
+of shrinkwraprs to be enabled",
+    ));
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_UUID) {
+    validate_uuid(&details, &input)?;
+  }
+  if let Some(encoding) = details.display_encoding {
+    validate_display_encoding(&details, &input, encoding)?;
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_PATH_LIKE) && !cfg!(feature = "std") {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(path_like)] needs the `std` feature to be enabled",
+    ));
+  }
+  if !details.flags.contains(ShrinkwrapFlags::SW_IGNORE_BORROW) {
+    let warnings = validate_borrow_contract(&attrs, &details, &input)?;
+
+    details.warnings.extend(warnings);
+  }
+
+  Ok((details, input))
+}
+
+/// `Borrow` promises that the wrapper hashes and compares the same as what it
+/// borrows as, which derived impls break if there are other fields for them
+/// to look at, and hash maps keyed by the wrapper quietly stop finding
+/// things. That's bad enough to refuse for `Hash`, `Eq` and `Ord`, which the
+/// contract names; `PartialEq` and `PartialOrd` only get a warning. We only
+/// see the derives listed after ours, so this can't catch everything.
+fn validate_borrow_contract(
+  attrs: &[syn::Attribute],
+  details: &StructDetails,
+  input: &Struct,
+) -> syn::Result<Vec<Warning>> {
+  use syn::{Meta, NestedMeta};
+
+  let borrows = details.traits.contains(Traits::BORROW)
+    || input.other_fields.iter().any(|other| other.options.borrow);
+
+  if !borrows || extra_field(input).is_none() {
+    return Ok(vec![]);
+  }
+
+  let derived = tagged_attrs("derive", attrs)?
+    .into_iter()
+    .filter_map(|meta| match meta {
+      NestedMeta::Meta(Meta::Path(path)) => path.segments.last().cloned(),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  let message = |ident: &syn::Ident| {
+    format!(
+      "shrinkwraprs: the derived `{0}` looks at every field, but
+`Borrow` promises that the wrapper behaves the same as
+what it borrows as, so hash maps and sets keyed by this
+struct would misbehave. Implement `{0}` by hand using
+just the main field, or leave out `Borrow` with
+#[shrinkwrap(no_borrow)]. If you're sure, silence this
+with #[shrinkwrap(unsafe_ignore_borrow_contract)].",
+      ident
+    )
+  };
+
+  let broken = derived
+    .iter()
+    .find(|segment| ["Hash", "Eq", "Ord"].iter().any(|name| segment.ident == name));
+  if let Some(segment) = broken {
+    return Err(syn::Error::new_spanned(&segment.ident, message(&segment.ident)));
+  }
+
+  let warnings = derived
+    .iter()
+    .filter(|segment| ["PartialEq", "PartialOrd"].iter().any(|name| segment.ident == name))
+    .map(|segment| Warning {
+      span: segment.ident.span(),
+      message: message(&segment.ident),
+    })
+    .collect();
+
+  Ok(warnings)
+}
+
+/// We need every field's options to figure out which one is the main field,
+/// so we parse them all up front.
+fn with_options<I>(fields: I) -> syn::Result<Fields>
+where
+  I: IntoIterator<Item = syn::Field>,
+{
+  fields
+    .into_iter()
+    .map(|field| {
+      let options = parse_field_options(&field)?;
+
+      Ok((field, options))
+    })
+    .collect()
+}
+
+fn is_repr_transparent(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+  use syn::{Meta, NestedMeta};
+
+  Ok(tagged_attrs("repr", attrs)?.into_iter().any(|meta| match meta {
+    NestedMeta::Meta(Meta::Path(path)) => path.is_ident("transparent"),
+    _ => false,
+  }))
+}
+
+/// The first field other than the main field that isn't a `PhantomData`, for
+/// the options that have to build the wrapper out of just the inner value.
+fn extra_field(input: &Struct) -> Option<&syn::Field> {
+  input
+    .other_fields
+    .iter()
+    .map(|other| &other.field)
+    .find(|field| !is_phantom_data(&field.ty))
+}
+
+/// The borrowed half of a pair wraps something unsized like `str`, so we
+/// can only generate what works through references, and the owned half
+/// needs to be able to cast references to its target into references to us.
+fn validate_owned(
+  attrs: &[syn::Attribute],
+  details: &StructDetails,
+  input: &Struct,
+) -> syn::Result<()> {
+  if !is_repr_transparent(attrs)? {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(owned = \"..\")] requires the struct to
+also be marked #[repr(transparent)], so that the owned
+half can cast references to its contents into references
+to this struct.",
+    ));
+  }
+
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(owned = \"..\")] requires every field
+other than the main field to be a PhantomData.",
+    ));
+  }
+
+  if !(details.flags - ShrinkwrapFlags::SW_UNSIZED).is_empty()
+    || details.constructor.is_some()
+    || details.ext_trait.is_some()
+    || details.borrowed.is_some()
+    || !details.delegates.constructors.is_empty()
+    || !details.delegates.owned.is_empty()
+  {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: the borrowed half of a pair, with
+#[shrinkwrap(owned = \"..\")], wraps an unsized value, so
+it can't take options that need to move the value around.",
+    ));
+  }
+
+  if !HAS_ALLOC {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(owned = \"..\")] needs the `std` or `alloc` feature
+to be enabled",
+    ));
+  }
+
+  Ok(())
+}
+
+/// Like the borrowed half of a pair, but on its own: a `struct Label(str)`
+/// is only ever seen through references, which it can be cast from.
+fn validate_unsized(
+  attrs: &[syn::Attribute],
+  details: &StructDetails,
+  input: &Struct,
+) -> syn::Result<()> {
+  if !is_repr_transparent(attrs)? {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: a struct around an unsized value has to be
+marked #[repr(transparent)], so that references to the
+value can be cast into references to the struct.",
+    ));
+  }
+
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: a struct around an unsized value requires
+every field other than the main field to be a PhantomData.",
+    ));
+  }
+
+  if !(details.flags - ShrinkwrapFlags::SW_UNSIZED).is_empty()
+    || details.constructor.is_some()
+    || details.ext_trait.is_some()
+    || details.borrowed.is_some()
+    || !details.delegates.constructors.is_empty()
+    || !details.delegates.owned.is_empty()
+  {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: this struct wraps an unsized value, so it
+can't take options that need to move the value around.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// Types that are unsized no matter what: `str`, slices and trait objects.
+/// Anything else, like `Path`, needs `#[shrinkwrap(unsized_inner)]`.
+fn is_unsized(ty: &syn::Type) -> bool {
+  match ty {
+    syn::Type::Slice(..) | syn::Type::TraitObject(..) => true,
+    syn::Type::Path(syn::TypePath { qself: None, path }) => path.is_ident("str"),
+    syn::Type::Paren(syn::TypeParen { elem, .. }) | syn::Type::Group(syn::TypeGroup { elem, .. }) => {
+      is_unsized(elem)
+    }
+    _ => false,
+  }
+}
+
+/// `slice` derefs an `[T; N]` to `[T]`, which we can point `deref_target` at
+/// since arrays coerce to slices on their own.
+fn validate_slice(details: &mut StructDetails, input: &Struct) -> syn::Result<()> {
+  if details.deref_target.is_some() || details.flags.contains(ShrinkwrapFlags::SW_DEREF_VIA) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(slice)] already picks the deref
+target, so it can't be combined with deref_target
+or deref_via_inner.",
+    ));
+  }
+
+  let element = array_element(&input.inner_type).ok_or_else(|| {
+    syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(slice)] needs the main field to be
+an array, like `[u8; 16]`.",
+    )
+  })?;
+  details.deref_target = Some(syn::parse_quote!( [#element] ));
+
+  Ok(())
+}
+
+/// The `T` in an `[T; N]`.
+pub fn array_element(ty: &syn::Type) -> Option<&syn::Type> {
+  match ty {
+    syn::Type::Array(syn::TypeArray { elem, .. }) => Some(elem),
+    syn::Type::Paren(syn::TypeParen { elem, .. }) | syn::Type::Group(syn::TypeGroup { elem, .. }) => {
+      array_element(elem)
+    }
+    _ => None,
+  }
+}
+
+/// Whose guards `lock` hands out, from `#[shrinkwrap(lock = "..")]`. This is
+/// per struct rather than a feature, since a crate can use both kinds of
+/// lock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockBackend {
+  /// `std::sync`'s, from plain `#[shrinkwrap(lock)]`.
+  Std,
+  /// `parking_lot`'s, from `#[shrinkwrap(lock = "parking_lot")]`.
+  ParkingLot,
+}
+
+/// Which lock `lock` is passing through to.
+pub enum Lock<'a> {
+  Mutex(&'a syn::Type),
+  RwLock(&'a syn::Type),
+}
+
+/// `Mutex<T>` or `RwLock<T>`, going by the name, so that `parking_lot`'s
+/// work too.
+pub fn lock_inner<'a>(ty: &'a syn::Type) -> Option<Lock<'a>> {
+  let (ident, inner) = single_type_argument(ty)?;
+
+  if ident == "Mutex" {
+    Some(Lock::Mutex(inner))
+  } else if ident == "RwLock" {
+    Some(Lock::RwLock(inner))
+  } else {
+    None
+  }
+}
+
+/// Which cell `cell` is passing through to.
+pub enum Cell<'a> {
+  Cell(&'a syn::Type),
+  RefCell(&'a syn::Type),
+}
+
+/// `Cell<T>` or `RefCell<T>`, going by the name.
+pub fn cell_inner<'a>(ty: &'a syn::Type) -> Option<Cell<'a>> {
+  let (ident, inner) = single_type_argument(ty)?;
+
+  if ident == "Cell" {
+    Some(Cell::Cell(inner))
+  } else if ident == "RefCell" {
+    Some(Cell::RefCell(inner))
+  } else {
+    None
+  }
+}
+
+/// The value an atomic like `AtomicU64` holds, going by the name.
+pub fn atomic_value(ty: &syn::Type) -> Option<syn::Ident> {
+  let segment = match ty {
+    syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+    _ => return None,
+  };
+  let atomics = [
+    ("AtomicBool", "bool"),
+    ("AtomicI8", "i8"),
+    ("AtomicI16", "i16"),
+    ("AtomicI32", "i32"),
+    ("AtomicI64", "i64"),
+    ("AtomicIsize", "isize"),
+    ("AtomicU8", "u8"),
+    ("AtomicU16", "u16"),
+    ("AtomicU32", "u32"),
+    ("AtomicU64", "u64"),
+    ("AtomicUsize", "usize"),
+  ];
+
+  atomics
+    .iter()
+    .find(|&&(atomic, _)| segment.ident == atomic && segment.arguments.is_empty())
+    .map(|&(_, value)| syn::Ident::new(value, segment.ident.span()))
+}
+
+/// The name of a type like `Mutex<T>`, along with the `T`.
+fn single_type_argument(ty: &syn::Type) -> Option<(&syn::Ident, &syn::Type)> {
+  let segment = match ty {
+    syn::Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+    _ => return None,
+  };
+
+  match &segment.arguments {
+    syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+      syn::GenericArgument::Type(ty) => Some((&segment.ident, ty)),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// A `Cell`'s `get()`, `set()` and `replace()` would be defined twice
+/// alongside `copy_get` or `mem_helpers`, which have their own.
+fn validate_cell(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  match cell_inner(&input.inner_type) {
+    None => Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(cell)] needs the main field to be
+a `Cell<T>` or a `RefCell<T>`.",
+    )),
+    Some(Cell::Cell(_)) if details.flags.contains(ShrinkwrapFlags::SW_COPY_GET) => {
+      Err(syn::Error::new_spanned(
+        &details.ident,
+        "shrinkwraprs: #[shrinkwrap(cell)] already generates a `get()` for
+a `Cell`, so it can't be combined with copy_get.",
+      ))
+    }
+    Some(Cell::Cell(_)) if details.flags.contains(ShrinkwrapFlags::SW_MEM_HELPERS) => {
+      Err(syn::Error::new_spanned(
+        &details.ident,
+        "shrinkwraprs: #[shrinkwrap(cell)] already generates `set()` and
+`replace()` for a `Cell`, so it can't be combined with
+mem_helpers.",
+      ))
+    }
+    Some(_) => Ok(()),
+  }
+}
+
+fn validate_lock(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if lock_inner(&input.inner_type).is_none() {
+    return Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(lock)] needs the main field to be
+a `Mutex<T>` or an `RwLock<T>`.",
+    ));
+  }
+
+  match details.lock_backend {
+    LockBackend::Std if !cfg!(feature = "std") => Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(lock)] needs the `std` feature to be enabled",
+    )),
+    LockBackend::ParkingLot if !cfg!(feature = "parking_lot") => Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(lock = \"parking_lot\")] needs the `parking_lot`
+feature of shrinkwraprs to be enabled",
+    )),
+    _ => Ok(()),
+  }
+}
+
+/// Whether a generic parameter's name turns up anywhere in some tokens. It
+/// only looks at names, so it can be fooled by a type with the same name as
+/// a lifetime, but that's not something anyone writes.
+pub fn mentions<T: quote::ToTokens>(tokens: &T, param: &syn::GenericParam) -> bool {
+  fn walk(tokens: proc_macro2::TokenStream, name: &syn::Ident) -> bool {
+    tokens.into_iter().any(|tree| match tree {
+      proc_macro2::TokenTree::Ident(ident) => ident == *name,
+      proc_macro2::TokenTree::Group(group) => walk(group.stream(), name),
+      _ => false,
+    })
+  }
+
+  let name = match param {
+    syn::GenericParam::Type(param) => &param.ident,
+    syn::GenericParam::Lifetime(param) => &param.lifetime.ident,
+    syn::GenericParam::Const(param) => &param.ident,
+  };
+
+  walk(tokens.to_token_stream(), name)
+}
+
+/// `#[shrinkwrap(main_field_mut = "none")]` opts a struct out of mutable
+/// access, even if it's asked for in options shared with other structs.
+fn validate_no_mut(details: &mut StructDetails, input: &Struct) -> syn::Result<()> {
+  if let Some(other) = input.other_fields.iter().find(|other| other.options.main_field_mut) {
+    return Err(syn::Error::new_spanned(
+      &other.field,
+      "shrinkwraprs: #[shrinkwrap(main_field_mut = \"none\")] means no field
+is mutable, so no field can be marked main_field_mut.",
+    ));
+  }
+  if let Some(vis) = &details.mut_access {
+    return Err(syn::Error::new_spanned(
+      vis,
+      "shrinkwraprs: #[shrinkwrap(main_field_mut = \"none\")] means no field
+is mutable, so there's nothing for mut_access to give
+access to.",
+    ));
+  }
+
+  details.flags.remove(ShrinkwrapFlags::SW_MUT);
+
+  Ok(())
+}
+
+/// Structs declared with `newtype!` only hold values that passed its check,
+/// which anything that writes to the main field would skip.
+fn validate_checked(details: &StructDetails) -> syn::Result<()> {
+  if details.flags.contains(ShrinkwrapFlags::SW_MUT) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: newtype! structs can't be #[shrinkwrap(mutable)],
+since writing through `DerefMut` or `set()` would skip the
+check.",
+    ));
+  }
+  if let Some(vis) = &details.mut_access {
+    return Err(syn::Error::new_spanned(
+      vis,
+      "shrinkwraprs: newtype! structs can't have mut_access, since
+writing through `inner_mut()` would skip the check.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// The owned half of a pair derefs to the borrowed half, so it can't deref
+/// anywhere else.
+fn validate_borrowed(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if details.deref_target.is_some() || details.flags.contains(ShrinkwrapFlags::SW_DEREF_VIA) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(borrowed = \"..\")] already picks the
+deref target, so it can't be combined with deref_target
+or deref_via_inner.",
+    ));
+  }
+
+  let message = "shrinkwraprs: #[shrinkwrap(borrowed = \"..\")] requires every field
+other than the main field to be a PhantomData, since
+`ToOwned` has to build this struct out of just an owned
+copy of the borrowed half's contents.";
+
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(field, message));
+  }
+  if input.nested {
+    return Err(syn::Error::new_spanned(&details.ident, message));
+  }
+
+  if !HAS_ALLOC {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(borrowed = \"..\")] needs the `std` or `alloc`
+feature to be enabled",
+    ));
+  }
+
+  // `ToOwned` is implemented on the borrowed half, so that's all it can get
+  // generics from. Anything else about the owned half needs a default.
+  let borrowed = details.borrowed.as_ref().expect("only called for the owned half");
+  let unconstrained = details.generics.params.iter().find(|param| {
+    let has_default = match param {
+      syn::GenericParam::Type(param) => param.default.is_some(),
+      syn::GenericParam::Lifetime(_) => false,
+      syn::GenericParam::Const(param) => param.default.is_some(),
+    };
+
+    !has_default && !mentions(borrowed, param)
+  });
+  if details.traits.contains(Traits::BORROW) {
+    if let Some(param) = unconstrained {
+      return Err(syn::Error::new_spanned(
+        param,
+        "shrinkwraprs: #[shrinkwrap(borrowed = \"..\")] needs every generic
+parameter of the owned half to either appear in the
+borrowed half or have a default, since `ToOwned` has
+to pick one owned type for each borrowed type.",
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+/// Pretty much everything we generate hands out references to the inner
+/// field, and references to fields of packed structs might be unaligned.
+fn validate_not_packed(attrs: &[syn::Attribute]) -> syn::Result<()> {
+  use syn::{Meta, MetaList, NestedMeta};
+
+  let packed = tagged_attrs("repr", attrs)?.into_iter().find(|meta| match meta {
+    NestedMeta::Meta(Meta::Path(path)) | NestedMeta::Meta(Meta::List(MetaList { path, .. })) => {
+      path.is_ident("packed")
+    }
+    _ => false,
+  });
+
+  match packed {
+    Some(packed) => Err(syn::Error::new_spanned(
+      packed,
+      "shrinkwraprs: cowardly refusing to derive anything for a
+#[repr(packed)] struct, since references to the inner
+field could be unaligned, which is undefined behavior.",
+    )),
+    None => Ok(()),
+  }
+}
+
+/// Trait methods can't be called in const fns, so there's no way for a const
+/// constructor to convert its argument or to fill in fields with defaults.
+fn validate_const_fns(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if details.flags.contains(ShrinkwrapFlags::SW_CTOR_INTO) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(const_fns)] can't be combined with
+#[shrinkwrap(constructor_into)], since calling .into()
+isn't allowed in const fns.",
+    ));
+  }
+
+  match extra_field(input) {
+    Some(field) if details.constructor.is_some() => Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(const_fns)] can't generate a const
+constructor for a struct with fields other than the main
+field (besides PhantomData), since filling them in with
+Default::default() isn't allowed in const fns.",
+    )),
+    _ => Ok(()),
+  }
+}
+
+/// Two fields marked `#[shrinkwrap(borrow)]` with the same type (or one with
+/// the main field's type) would give us two overlapping `Borrow` impls, and
+/// rustc's complaints about those don't point anywhere useful.
+fn validate_borrow_targets(input: &Struct) -> syn::Result<()> {
+  let type_name = |ty: &syn::Type| quote!(#ty).to_string();
+  let member_name = |member: &proc_macro2::TokenStream| member.to_string().replace(' ', "");
+
+  let main = (&input.inner_field, type_name(&input.inner_type));
+  let mut seen = vec![main];
+
+  for other in input.other_fields.iter().filter(|other| other.options.borrow) {
+    let ty = type_name(&other.field.ty);
+
+    if let Some((clash, _)) = seen.iter().find(|(_, seen_ty)| *seen_ty == ty) {
+      return Err(syn::Error::new_spanned(
+        &other.field,
+        format!(
+          "shrinkwraprs: fields `{}` and `{}` both have type `{}`,
+so the wrapper can't borrow as both of them.",
+          member_name(clash),
+          member_name(&other.member),
+          ty
+        ),
+      ));
+    }
+
+    seen.push((&other.member, ty));
+  }
+
+  Ok(())
+}
+
+/// A blanket `AsRef` impl overlaps with every other `AsRef` impl we could
+/// generate, so it has to be the only one.
+fn validate_forward_as_ref(details: &StructDetails) -> syn::Result<()> {
+  let overlapping = [
+    (ShrinkwrapFlags::SW_AS_REF_ALL, "as_ref_all"),
+    (ShrinkwrapFlags::SW_TRANSITIVE, "transitive"),
+    (ShrinkwrapFlags::SW_PATH_LIKE, "path_like"),
+    (ShrinkwrapFlags::SW_BYTES, "bytes"),
+    (ShrinkwrapFlags::SW_AS_REF_VIA, "as_ref_via_inner"),
+    (ShrinkwrapFlags::SW_SLICE, "slice"),
+  ];
+
+  for &(flag, name) in &overlapping {
+    if details.flags.contains(flag) {
+      return Err(syn::Error::new_spanned(
+        &details.ident,
+        format!(
+          "shrinkwraprs: #[shrinkwrap(forward_as_ref)] already covers every
+`AsRef` the inner type has, so it can't be combined with
+#[shrinkwrap({})].",
+          name
+        ),
+      ));
+    }
+  }
+
+  Ok(())
+}
+
+/// Same goes for a blanket `Borrow` impl.
+fn validate_forward_borrow(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  let message = "shrinkwraprs: #[shrinkwrap(forward_borrow)] already covers every
+`Borrow` the inner type has, so it can't be combined with
+#[shrinkwrap(transitive)] or #[shrinkwrap(borrow)] fields.";
+
+  if details.flags.contains(ShrinkwrapFlags::SW_TRANSITIVE) {
+    return Err(syn::Error::new_spanned(&details.ident, message));
+  }
+
+  match input.other_fields.iter().find(|other| other.options.borrow) {
+    Some(other) => Err(syn::Error::new_spanned(&other.field, message)),
+    None => Ok(()),
+  }
+}
+
+/// The extension trait has to conjure a wrapper out of nothing but the inner
+/// value.
+fn validate_ext_trait(input: &Struct) -> syn::Result<()> {
+  match extra_field(input) {
+    Some(field) => Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(ext_trait)] requires every field
+other than the main field to be a PhantomData, since the
+trait method only gets the inner value to build the
+wrapper from.",
+    )),
+    None => Ok(()),
+  }
+}
+
+/// `#[shrinkwrap(uuid)]` calls straight into the `uuid` crate, so it needs
+/// the feature that pulls that in, and a `Uuid` to wrap with nothing else
+/// that its constructors would have to fill in.
+fn validate_uuid(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if !cfg!(feature = "uuid") {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(uuid)] needs the `uuid` feature
+of shrinkwraprs to be enabled",
+    ));
+  }
+
+  let is_uuid = match &input.inner_type {
+    syn::Type::Path(syn::TypePath { qself: None, path }) => {
+      path.segments.last().is_some_and(|segment| segment.ident == "Uuid")
+    }
+    _ => false,
+  };
+  if !is_uuid {
+    return Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(uuid)] needs the main field to be
+a `Uuid`.",
+    ));
+  }
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(uuid)] requires every field other
+than the main field to be a PhantomData, since its
+constructors only build the `Uuid`.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// Whether the main field is made of bytes that `display_encoding` can spell
+/// out: an `[u8; N]`, or a `Vec<u8>`.
+pub fn is_byte_vec(ty: &syn::Type) -> bool {
+  let u8_type: syn::Type = syn::parse_quote!(u8);
+
+  match single_type_argument(ty) {
+    Some((ident, inner)) => ident == "Vec" && *inner == u8_type,
+    None => false,
+  }
+}
+
+fn validate_display_encoding(
+  details: &StructDetails,
+  input: &Struct,
+  encoding: Encoding,
+) -> syn::Result<()> {
+  let u8_type: syn::Type = syn::parse_quote!(u8);
+  let is_byte_array = array_element(&input.inner_type) == Some(&u8_type);
+  let is_byte_vec = is_byte_vec(&input.inner_type);
+
+  if !is_byte_array && !is_byte_vec {
+    return Err(syn::Error::new_spanned(
+      &input.inner_type,
+      "shrinkwraprs: #[shrinkwrap(display_encoding = \"..\")] needs the main
+field to be an `[u8; N]` or a `Vec<u8>`.",
+    ));
+  }
+  if (is_byte_vec || encoding == Encoding::Base58) && !HAS_ALLOC {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(display_encoding = \"..\")] needs the `std` or
+`alloc` feature to be enabled for `Vec<u8>` and for base58",
+    ));
+  }
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(display_encoding = \"..\")] requires every
+field other than the main field to be a PhantomData, since
+`FromStr` only parses the bytes.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// Delegated constructors rewrap whatever the inner type's constructor
+/// returns, so that had better be the inner type, and there had better be
+/// a way to build the wrapper from just that.
+fn validate_delegate_constructors(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if let Some(field) = extra_field(input) {
+    return Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(delegate_constructors(..))] requires
+every field other than the main field to be a PhantomData,
+since the inner type's constructors only build the inner
+value.",
+    ));
+  }
+
+  for sig in &details.delegates.constructors {
+    if let Some(receiver) = sig.receiver() {
+      return Err(syn::Error::new_spanned(
+        receiver,
+        format!(
+          "shrinkwraprs: delegated constructors can't take `self`; `{}`
+looks like a method.",
+          sig.ident
+        ),
+      ));
+    }
+    let no_return_type = sig.output == syn::ReturnType::Default;
+
+    if !no_return_type && !returns_inner(sig, &input.inner_type) {
+      return Err(syn::Error::new_spanned(
+        &sig.output,
+        format!(
+          "shrinkwraprs: delegated constructors have to return the inner
+type, but `{}` doesn't.",
+          sig.ident
+        ),
+      ));
+    }
+    delegate_args(sig)?;
+  }
+
+  Ok(())
+}
+
+/// Delegated methods are called on the unwrapped inner value, so they have to
+/// take it by value; anything taking `&self` is already reachable through
+/// `Deref`.
+fn validate_delegate_owned(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  let constructible = extra_field(input).is_none();
+
+  for sig in &details.delegates.owned {
+    match sig.receiver() {
+      Some(syn::FnArg::Receiver(syn::Receiver {
+        reference: None, ..
+      })) => (),
+      _ => {
+        return Err(syn::Error::new_spanned(
+          sig,
+          format!(
+            "shrinkwraprs: delegated owned methods have to take `self` by
+value, like `into_bytes(self) -> Vec<u8>`, but `{}` doesn't.",
+            sig.ident
+          ),
+        ))
+      }
+    }
+    if returns_inner(sig, &input.inner_type) && !constructible {
+      return Err(syn::Error::new_spanned(
+        &sig.output,
+        format!(
+          "shrinkwraprs: `{}` returns the inner type, but the result can
+only be rewrapped if every field other than the main
+field is a PhantomData.",
+          sig.ident
+        ),
+      ));
+    }
+    delegate_args(sig)?;
+  }
+
+  Ok(())
+}
+
+/// Whether a delegated function returns the inner type, either by name or as
+/// `Self` (which, from the inner type's point of view, is the same thing).
+pub fn returns_inner(sig: &syn::Signature, inner_type: &syn::Type) -> bool {
+  use quote::ToTokens;
+
+  match &sig.output {
+    syn::ReturnType::Default => false,
+    syn::ReturnType::Type(_, ty) => {
+      let ty = ty.to_token_stream().to_string();
+
+      ty == "Self" || ty == inner_type.to_token_stream().to_string()
+    }
+  }
+}
+
+/// The names of the arguments of a delegated function, so we can pass them
+/// along.
+pub fn delegate_args(sig: &syn::Signature) -> syn::Result<Vec<syn::Ident>> {
+  sig
+    .inputs
+    .iter()
+    .filter_map(|arg| match arg {
+      syn::FnArg::Receiver(..) => None,
+      syn::FnArg::Typed(syn::PatType { pat, .. }) => match &**pat {
+        syn::Pat::Ident(syn::PatIdent { ident, .. }) => Some(Ok(ident.clone())),
+        _ => Some(Err(syn::Error::new_spanned(
+          pat,
+          "shrinkwraprs: arguments of delegated functions have to be plain
+names, like `with_capacity(capacity: usize)`.",
+        ))),
+      },
+    })
+    .collect()
+}
+
+/// The setters are named after the fields, so there have to be names.
+fn validate_with_fields(input: &Struct) -> syn::Result<()> {
+  match input.other_fields.iter().find(|other| other.field.ident.is_none()) {
+    Some(other) => Err(syn::Error::new_spanned(
+      &other.field,
+      "shrinkwraprs: #[shrinkwrap(with_fields)] names its setters after
+the other fields, so it only works on structs with
+named fields.",
+    )),
+    None => Ok(()),
+  }
+}
+
+/// Casting between references to the inner type and references to the
+/// wrapper is only sound if the compiler guarantees they have the same
+/// layout, and if there's no other data in the wrapper we'd be conjuring
+/// out of thin air.
+fn validate_transparent(
+  attrs: &[syn::Attribute],
+  details: &StructDetails,
+  input: &Struct,
+) -> syn::Result<()> {
+  if !is_repr_transparent(attrs)? {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(transparent)] requires the struct to
+also be marked #[repr(transparent)], otherwise the
+layout of the wrapper isn't guaranteed to match the
+layout of the inner field.",
+    ));
+  }
+
+  match extra_field(input) {
+    Some(field) => Err(syn::Error::new_spanned(
+      field,
+      "shrinkwraprs: #[shrinkwrap(transparent)] requires every field
+other than the main field to be a PhantomData, since
+casting a reference to the inner field can't fill them
+in.",
+    )),
+    None => Ok(()),
+  }
+}
+
+/// The layout assertions are checked once, when the crate is compiled, so
+/// there has to be exactly one layout to check.
+fn validate_zero_cost(details: &StructDetails) -> syn::Result<()> {
+  if details.unsized_inner {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(assert_zero_cost)] can't check a
+struct around an unsized value, which has no fixed size.",
+    ));
+  }
+
+  match details.generics.params.first() {
+    Some(param) => Err(syn::Error::new_spanned(
+      param,
+      "shrinkwraprs: #[shrinkwrap(assert_zero_cost)] only works on
+structs without generic parameters, since the layout of
+a generic struct depends on what it's instantiated with.",
+    )),
+    None => Ok(()),
+  }
+}
+
+/// The generated tests build the wrapper out of a sample inner value, the way
+/// `new()` would, and have to be able to name all of the types involved.
+fn validate_gen_tests(details: &StructDetails, input: &Struct) -> syn::Result<()> {
+  if details.unsized_inner {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(gen_tests)] can't test a struct
+around an unsized value, since it can't be built out of
+a sample value. Put it on the owned half instead.",
+    ));
+  }
+
+  if let Some(param) = details.generics.params.first() {
+    return Err(syn::Error::new_spanned(
+      param,
+      "shrinkwraprs: #[shrinkwrap(gen_tests)] only works on structs
+without generic parameters, since the tests would have to
+pick what to instantiate them with.",
+    ));
+  }
+
+  if input.nested {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[shrinkwrap(gen_tests)] can't build the struct
+when main_field reaches into another struct, since
+there's no way to fill in the rest of that struct.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// The `T` in an `Option<T>`, going by the name.
+pub fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+  match single_type_argument(ty)? {
+    (ident, inner) if ident == "Option" => Some(inner),
+    _ => None,
+  }
+}
+
+pub fn is_phantom_data(ty: &syn::Type) -> bool {
+  match ty {
+    syn::Type::Path(syn::TypePath { path, .. }) => match path.segments.last() {
+      Some(segment) => segment.ident == "PhantomData",
+      None => false,
+    },
+    _ => false,
+  }
+}
+
+/// Specifically for working with attributes like #[shrinkwrap(..)], where
+/// a name is combined with a list of attributes. Get the list of attributes
+/// matching the tag. Other attributes are left alone, since they don't have
+/// to follow the usual meta syntax.
+pub fn tagged_attrs(tag: &str, attrs: &[syn::Attribute]) -> syn::Result<Vec<syn::NestedMeta>> {
+  use syn::{Meta, MetaList};
+
+  let mut result = vec![];
+
+  for attr in attrs.iter().filter(|attr| attr.path.is_ident(tag)) {
+    if let Meta::List(MetaList { nested, .. }) = attr.parse_meta()? {
+      result.extend(nested);
+    }
+  }
+
+  Ok(result)
+}
+
+/// Ignored fields don't count when figuring out which field is the main
+/// field. `PhantomData`s are ignored automatically, since they can't hold
+/// anything worth dereferencing to anyway.
+fn is_ignored((field, options): &(syn::Field, FieldOptions)) -> bool {
+  options.ignore || is_phantom_data(&field.ty)
+}
+
+/// Only a single field, out of all a struct's fields, can be marked as
+/// the main field that we deref to. So let's find that field.
+/// We also return the 0-based number of the marked field, along with the
+/// numbers of all the other fields. A main field picked on the struct itself
+/// counts as marked.
+fn find_marked_field(
+  details: &StructDetails,
+  fields: Fields,
+  main_field: &Option<MainField>,
+) -> syn::Result<(IndexedField, Vec<IndexedField>)> {
+  let is_selected = |index: usize, field: &syn::Field| match main_field {
+    Some(MainField::Named(name)) => match &field.ident {
+      Some(ident) => ident == name,
+      None => false,
+    },
+    Some(MainField::Index(selected)) => index == *selected,
+    None => false,
+  };
+
+  if let Some(main_field) = main_field {
+    if !fields.iter().enumerate().any(|(index, (field, _))| is_selected(index, field)) {
+      let message = match main_field {
+        MainField::Named(name) => format!(
+          "shrinkwraprs: main_field = {:?}, but there's no field with
+that name. If the field is behind #[cfg(..)], it doesn't
+exist in this build, so gate the option the same way with
+#[cfg_attr(..)].",
+          name
+        ),
+        MainField::Index(index) => format!(
+          "shrinkwraprs: main_field = {}, but there's no field with
+that index. Fields behind #[cfg(..)] don't count when
+they're disabled, so indices can shift between builds;
+consider picking the main field by name instead.",
+          index
+        ),
+      };
+
+      return Err(syn::Error::new_spanned(&details.ident, message));
+    }
+  }
+
+  let (marked, unmarked) = fields
+    .into_iter()
+    .enumerate()
+    .partition::<Vec<_>, _>(|(index, (field, options))| {
+      options.main_field || is_selected(*index, field)
+    });
+  let candidates = unmarked
+    .iter()
+    .filter(|(_, field)| !is_ignored(field))
+    .count();
+
+  if let [(_, (field, options))] = marked.as_slice() {
+    if options.ignore {
+      return Err(syn::Error::new_spanned(
+        field,
+        "shrinkwraprs: a field can't be both the main field and
+#[shrinkwrap(ignore)]d.",
+      ));
+    }
+  }
+  if let [_, (_, (second, _)), ..] = marked.as_slice() {
+    return Err(syn::Error::new_spanned(
+      second,
+      "halp! shrinkwraprs doesn't know which field you want
+this struct to convert to. Did you accidentally mark
+more than one field with #[shrinkwrap(main_field)]?",
+    ));
+  }
+
+  match (marked.into_iter().next(), candidates) {
+    (Some(field), _) => Ok((field, unmarked)),
+    // A lone `PhantomData` is still the only thing to deref to.
+    (None, 0) if unmarked.len() == 1 => Ok((unmarked.into_iter().next().unwrap(), vec![])),
+    (None, 1) => {
+      let (ignored, mut candidate) = unmarked
+        .into_iter()
+        .partition::<Vec<_>, _>(|(_, field)| is_ignored(field));
+
+      Ok((candidate.pop().unwrap(), ignored))
+    }
+    _ => Err(ambiguous_main_field(details, &unmarked)),
+  }
+}
+
+/// When we can't pick a main field, the most helpful thing we can do is list
+/// the fields it could have been, and point at each of them.
+fn ambiguous_main_field(details: &StructDetails, unmarked: &[IndexedField]) -> syn::Error {
+  let field_name = |index: usize, field: &syn::Field| match &field.ident {
+    Some(ident) => format!("`{}`", ident),
+    None => format!("`{}`", index),
+  };
+
+  let mut candidates = unmarked
+    .iter()
+    .filter(|(_, field)| !is_ignored(field))
+    .collect::<Vec<_>>();
+  if candidates.is_empty() {
+    candidates = unmarked.iter().collect::<Vec<_>>();
+  }
+
+  let names = candidates
+    .iter()
+    .map(|(index, (field, _))| field_name(*index, field))
+    .collect::<Vec<_>>()
+    .join(", ");
+  let (_, (example, _)) = candidates[0];
+  let annotation = match &example.ident {
+    Some(ident) => format!("#[shrinkwrap(main_field)] {}: {}", ident, type_name(&example.ty)),
+    None => format!("#[shrinkwrap(main_field)] {}", type_name(&example.ty)),
+  };
+
+  let mut error = syn::Error::new_spanned(
+    &details.ident,
+    format!(
+      "halp! shrinkwraprs doesn't know which field you want
+this struct to convert to; it could be any of
+{}. Did you forget to mark a field with
+#[shrinkwrap(main_field)]? For example:
+
+    {}
+
+If this struct only has one field in some builds, because
+the others are behind #[cfg(..)], mark the main field
+anyway so that every build agrees on it.",
+      names, annotation
+    ),
+  );
+
+  for (index, (field, _)) in candidates {
+    error.combine(syn::Error::new_spanned(
+      field,
+      format!(
+        "shrinkwraprs: {} could be the main field",
+        field_name(*index, field)
+      ),
+    ));
+  }
+
+  error
+}
+
+fn validate_tuple(
+  details: &StructDetails,
+  fields: Fields,
+  main_field: &Option<MainField>,
+  nested: Option<NestedField>,
+) -> syn::Result<Struct> {
+  if fields.is_empty() {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs requires tuple structs to have at least one
+field!",
+    ));
+  }
+
+  let ((marked_index, marked_field), other_fields) =
+    find_marked_field(details, fields, main_field)?;
+  let index = syn::Index {
+    index: marked_index as u32,
+    span: field_span(&marked_field.0),
+  };
+  let other_fields = other_fields
+    .into_iter()
+    .map(|(index, (field, options))| {
+      let index = syn::Index {
+        index: index as u32,
+        span: field_span(&field),
+      };
+
+      OtherField {
+        member: quote!( #index ),
+        field,
+        options,
+      }
+    })
+    .collect::<Vec<_>>();
+
+  build_struct(quote!( #index ), (marked_index, marked_field), other_fields, nested)
+}
+
+fn validate_nontuple(
+  details: &StructDetails,
+  fields: Fields,
+  main_field: &Option<MainField>,
+  nested: Option<NestedField>,
+) -> syn::Result<Struct> {
+  if fields.is_empty() {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs requires structs to have at least one
+field!",
+    ));
+  }
+
+  let ((marked_index, marked_field), other_fields) =
+    find_marked_field(details, fields, main_field)?;
+  let ident = marked_field.0.ident.clone().unwrap();
+  let other_fields = other_fields
+    .into_iter()
+    .map(|(_, (field, options))| {
+      let ident = field.ident.clone().unwrap();
+
+      OtherField {
+        member: quote!( #ident ),
+        field,
+        options,
+      }
+    })
+    .collect::<Vec<_>>();
+
+  build_struct(quote!( #ident ), (marked_index, marked_field), other_fields, nested)
+}
+
+/// Where a field's name is, or for tuple structs, where its type starts.
+pub fn field_span(field: &syn::Field) -> proc_macro2::Span {
+  use syn::spanned::Spanned;
+
+  match &field.ident {
+    Some(ident) => ident.span(),
+    None => field.ty.span(),
+  }
+}
+
+/// When the main field is nested, the field it's nested in is just another
+/// field as far as constructing and destructuring go.
+fn build_struct(
+  member: proc_macro2::TokenStream,
+  (marked_index, (marked_field, _)): IndexedField,
+  mut other_fields: Vec<OtherField>,
+  nested: Option<NestedField>,
+) -> syn::Result<Struct> {
+  let inner_visibility = marked_field.vis.clone();
+  let inner_span = field_span(&marked_field);
+  let inner_field = match &nested {
+    None => member.clone(),
+    Some(NestedField { path, .. }) => quote!( #member #(.#path)* ),
+  };
+
+  let marked_mut = other_fields
+    .iter()
+    .filter(|other| other.options.main_field_mut)
+    .collect::<Vec<_>>();
+  let inner_type = match &nested {
+    None => &marked_field.ty,
+    Some(NestedField { ty, .. }) => ty,
+  };
+  let (inner_field_mut, inner_visibility_mut, inner_span_mut) = match marked_mut.as_slice() {
+    [] => (inner_field.clone(), inner_visibility.clone(), inner_span),
+    // The mutable traits convert to the same type as the immutable ones, so
+    // anything else would only fail deep inside the generated code.
+    [other] if other.field.ty != *inner_type => {
+      return Err(syn::Error::new_spanned(
+        &other.field.ty,
+        format!(
+          "shrinkwraprs: the main_field_mut `{}` is a `{}`, but the main
+field `{}` is a `{}`. Writes go through the same
+conversions as reads, so both fields need the same type.",
+          other.member.to_string().replace(' ', ""),
+          type_name(&other.field.ty),
+          inner_field.to_string().replace(' ', ""),
+          type_name(inner_type),
+        ),
+      ))
+    }
+    [other] => (
+      other.member.clone(),
+      other.field.vis.clone(),
+      field_span(&other.field),
+    ),
+    [_, second, ..] => {
+      return Err(syn::Error::new_spanned(
+        &second.field,
+        "halp! shrinkwraprs doesn't know which field you want to
+mutably convert to. Did you accidentally mark more than one
+field with #[shrinkwrap(main_field_mut)]?",
+      ))
+    }
+  };
+
+  let input = match nested {
+    None => Struct {
+      inner_field,
+      inner_type: marked_field.ty,
+      inner_visibility,
+      inner_span,
+      inner_index: marked_index,
+      other_fields,
+      inner_field_mut,
+      inner_visibility_mut,
+      inner_span_mut,
+      inner_docs: marked_field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .cloned()
+        .collect(),
+      nested: false,
+    },
+    Some(NestedField { ty, .. }) => {
+      other_fields.insert(
+        marked_index,
+        OtherField {
+          member,
+          field: marked_field,
+          options: FieldOptions::default(),
+        },
+      );
+
+      Struct {
+        inner_field,
+        inner_type: ty,
+        inner_visibility,
+        inner_span,
+        inner_index: marked_index,
+        other_fields,
+        inner_field_mut,
+        inner_visibility_mut,
+        inner_span_mut,
+        inner_docs: vec![],
+        nested: true,
+      }
+    }
+  };
+
+  Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+  use syn;
+
+  use super::*;
+
+  #[test]
+  fn test_field_attribute_found() {
+    let input = r"
+      struct Foo {
+        field1: u32,
+        #[shrinkwrap(main_field)]
+        field2: u32
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    match strct.data {
+      syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+        let marked = fields.into_iter().filter(|field| parse_field_options(field).unwrap().main_field);
+        let mut marked = marked.collect::<Vec<_>>();
+        assert_eq!(marked.len(), 1);
+        let ident = marked.remove(0).ident.unwrap();
+
+        assert_eq!(&ident, "field2");
+      }
+      _ => panic!(),
+    }
+  }
+
+  #[test]
+  fn test_field_attribute_not_found() {
+    let input = r"
+      struct Foo {
+        field1: u32,
+        field2: u32
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    match strct.data {
+      syn::Data::Struct(syn::DataStruct { fields, .. }) => {
+        let marked = fields
+          .into_iter()
+          .filter(|field| parse_field_options(field).unwrap().main_field)
+          .collect::<Vec<_>>();
+        assert_eq!(marked.len(), 0);
+      }
+      _ => panic!(),
+    }
+  }
+
+  #[test]
+  fn test_transparent_requires_repr() {
+    let input = r"
+      #[shrinkwrap(transparent)]
+      struct Foo(u32);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("#[repr(transparent)]"));
+  }
+
+  #[test]
+  fn test_packed_rejected() {
+    let input = r"
+      #[repr(C, packed(2))]
+      struct Foo(u32);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("#[repr(packed)]"));
+  }
+
+  #[test]
+  fn test_delegate_constructor_rejects_self() {
+    let input = r#"
+      #[shrinkwrap(delegate_constructors("len(&self) -> usize"))]
+      struct Foo(Vec<u8>);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("looks like a method"));
+  }
+
+  #[test]
+  fn test_unknown_option_rejected() {
+    let input = r"
+      #[shrinkwrap(mutible)]
+      struct Foo(u32);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("isn't an option that shrinkwraprs"));
+  }
+
+  #[test]
+  fn test_struct_level_main_field_must_exist() {
+    let input = r#"
+      #[shrinkwrap(main_field = "adress")]
+      struct Mailbox {
+        owner: String,
+        addr: String,
+      }
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("no field with\nthat name"));
+  }
+
+  #[test]
+  fn test_borrow_targets_must_not_collide() {
+    let input = r"
+      struct Token {
+        #[shrinkwrap(main_field)]
+        text: String,
+        #[shrinkwrap(borrow)]
+        line: u32,
+        #[shrinkwrap(borrow)]
+        column: u32,
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("fields `line` and `column` both have type `u32`"));
+  }
+
+  // The compiler strips disabled fields before we ever see them, so all we
+  // can do is point people at why the field might be missing.
+  #[test]
+  fn test_missing_main_field_mentions_cfg() {
+    let input = r#"
+      #[shrinkwrap(main_field = "name")]
+      struct Mailbox {
+        addr: String,
+      }
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("behind #[cfg(..)]"));
+  }
+
+  #[test]
+  fn test_enum_rejected_with_suggestion() {
+    let input = r"
+      enum Shape {
+        Circle(f64),
+        Square(f64),
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap().to_string();
+
+    assert!(error.contains("`Shape` is an enum"));
+    assert!(error.contains("struct WrappedShape(Shape);"));
+  }
+
+  #[test]
+  fn test_ambiguous_main_field_lists_candidates() {
+    let input = r"
+      struct Mailbox {
+        owner: String,
+        #[shrinkwrap(ignore)]
+        id: u64,
+        addr: String,
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+    let messages = error.into_iter().map(|error| error.to_string()).collect::<Vec<_>>();
+
+    assert!(messages[0].contains("any of\n`owner`, `addr`."));
+    assert!(messages[0].contains("#[shrinkwrap(main_field)] owner: String"));
+    assert_eq!(messages.len(), 3);
+  }
+
+  #[test]
+  fn test_derived_hash_breaking_borrow_rejected() {
+    let input = r"
+      #[derive(Clone, Hash)]
+      struct Token {
+        #[shrinkwrap(main_field)]
+        text: String,
+        line: u32,
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("the derived `Hash` looks at every field"));
+  }
+
+  #[test]
+  fn test_borrow_contract_overrides() {
+    let validate = |input: &str| validate_derive_input(syn::parse_str(input).unwrap());
+
+    let (details, _) = validate(
+      r"
+      #[derive(PartialEq)]
+      struct Token(#[shrinkwrap(main_field)] String, u32);
+      ",
+    )
+    .unwrap();
+    assert_eq!(details.warnings.len(), 1);
+
+    assert!(validate(
+      r"
+      #[derive(Hash)]
+      #[shrinkwrap(no_borrow)]
+      struct Token(#[shrinkwrap(main_field)] String, u32);
+      "
+    )
+    .is_ok());
+    assert!(validate(
+      r"
+      #[derive(Hash)]
+      #[shrinkwrap(unsafe_ignore_borrow_contract)]
+      struct Token(#[shrinkwrap(main_field)] String, u32);
+      "
+    )
+    .is_ok());
+    assert!(validate(
+      r"
+      #[derive(Hash)]
+      struct Token(String, ::std::marker::PhantomData<u32>);
+      "
+    )
+    .is_ok());
+  }
+
+  #[test]
+  fn test_mut_access_excludes_mutable() {
+    let input = r#"
+      #[shrinkwrap(mutable, mut_access = "pub(crate)")]
+      struct Balance(u64);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("instead of the\nmutable traits"));
+  }
+
+  #[test]
+  fn test_assert_zero_cost_rejects_generics() {
+    let input = r"
+      #[shrinkwrap(assert_zero_cost)]
+      struct Id<T>(u64, PhantomData<T>);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("without generic parameters"));
+  }
+
+  #[test]
+  fn test_gen_tests_sample() {
+    let input = r#"
+      #[shrinkwrap(gen_tests = "String::from(\"hi\")")]
+      struct Email(String);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let (details, _) = validate_derive_input(strct).unwrap();
+
+    assert!(details.flags.contains(ShrinkwrapFlags::SW_GEN_TESTS));
+    assert!(details.test_sample.is_some());
+
+    let input = r#"
+      #[shrinkwrap(gen_tests = "not an expression;")]
+      struct Email(String);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("gen_tests should be given a value"));
+  }
+
+  #[test]
+  fn test_unsized_inner_detected() {
+    let input = r"
+      #[repr(transparent)]
+      struct Label(str);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let (details, _) = validate_derive_input(strct).unwrap();
+
+    assert!(details.unsized_inner);
+
+    let input = r"
+      struct Bytes([u8]);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("#[repr(transparent)]"));
+  }
+
+  #[test]
+  fn test_as_ref_via_inner_excludes_transitive() {
+    let input = r"
+      #[shrinkwrap(as_ref_via_inner, transitive)]
+      struct Outer(Inner);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("same\n`AsRef`"));
+  }
+
+  #[test]
+  fn test_option_needs_option_field() {
+    let input = r"
+      #[shrinkwrap(option)]
+      struct Nickname(String);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("an `Option<T>`"));
+  }
+
+  #[test]
+  fn test_slice_needs_array() {
+    let input = r"
+      #[shrinkwrap(slice)]
+      struct Buffer(Vec<u8>);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("be\nan array"));
+  }
+
+  #[test]
+  fn test_lock_needs_lock() {
+    let input = r"
+      #[shrinkwrap(lock)]
+      struct Counter(Cell<u32>);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("a `Mutex<T>` or an `RwLock<T>`"));
+  }
+
+  #[test]
+  fn test_cell_needs_cell() {
+    let input = r"
+      #[shrinkwrap(cell)]
+      struct Counter(Mutex<u32>);
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("a `Cell<T>` or a `RefCell<T>`"));
+  }
+
+  #[test]
+  fn test_atomic_value() {
+    let atomic: syn::Type = syn::parse_str("std::sync::atomic::AtomicU64").unwrap();
+    let not_atomic: syn::Type = syn::parse_str("AtomicPtr<u8>").unwrap();
+
+    assert_eq!(atomic_value(&atomic).unwrap(), "u64");
+    assert!(atomic_value(&not_atomic).is_none());
+  }
+
+  #[test]
+  fn test_display_encoding_needs_bytes() {
+    let input = r#"
+      #[shrinkwrap(display_encoding = "hex")]
+      struct Digest([u16; 4]);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("an `[u8; N]` or a `Vec<u8>`"));
+  }
+
+  #[test]
+  fn test_lock_backend() {
+    let input = r#"
+      #[shrinkwrap(lock = "tokio")]
+      struct Counter(Mutex<u32>);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("\"std\" or \"parking_lot\""));
+  }
+
+  #[test]
+  fn test_owned_half_generics_need_borrowed_half() {
+    let input = r#"
+      #[shrinkwrap(borrowed = "List<T>")]
+      struct ListBuf<T, A>(#[shrinkwrap(main_field)] Vec<T>, PhantomData<A>);
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap();
+
+    assert!(error.to_string().contains("appear in the\nborrowed half or have a default"));
+  }
+
+  #[test]
+  fn test_main_field_mut_same_type() {
+    let input = r#"
+      struct Draft {
+        #[shrinkwrap(main_field)]
+        published: String,
+        #[shrinkwrap(main_field_mut)]
+        pending: Vec<u8>,
+      }
+    "#;
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let error = validate_derive_input(strct).err().unwrap().to_string();
+
+    assert!(error.contains("`pending` is a `Vec<u8>`"));
+    assert!(error.contains("`published` is a `String`"));
+  }
+
+  #[test]
+  fn test_no_mut_field() {
+    let marked = r#"
+      #[shrinkwrap(main_field_mut = "none")]
+      struct Draft {
+        #[shrinkwrap(main_field)]
+        published: String,
+        #[shrinkwrap(main_field_mut)]
+        pending: String,
+      }
+    "#;
+    let named = r#"
+      #[shrinkwrap(main_field_mut = "pending")]
+      struct Draft(String);
+    "#;
+
+    let marked: syn::DeriveInput = syn::parse_str(marked).unwrap();
+    let named: syn::DeriveInput = syn::parse_str(named).unwrap();
+    let marked = validate_derive_input(marked).err().unwrap();
+    let named = validate_derive_input(named).err().unwrap();
+
+    assert!(marked.to_string().contains("no field can be marked main_field_mut"));
+    assert!(named.to_string().contains("can only be \"none\""));
+  }
+
+  #[test]
+  fn test_checked_not_mutable() {
+    let mutable = r#"
+      #[shrinkwrap(__checked)]
+      #[shrinkwrap(mutable)]
+      struct Port(u16);
+    "#;
+    let mut_access = r#"
+      #[shrinkwrap(__checked)]
+      #[shrinkwrap(mut_access = "pub(crate)")]
+      struct Port(u16);
+    "#;
+
+    let mutable: syn::DeriveInput = syn::parse_str(mutable).unwrap();
+    let mut_access: syn::DeriveInput = syn::parse_str(mut_access).unwrap();
+    let mutable = validate_derive_input(mutable).err().unwrap();
+    let mut_access = validate_derive_input(mut_access).err().unwrap();
+
+    assert!(mutable.to_string().contains("can't be #[shrinkwrap(mutable)]"));
+    assert!(mut_access.to_string().contains("can't have mut_access"));
+  }
+
+  #[test]
+  fn test_cell_conflicts() {
+    let copy_get = r#"
+      #[shrinkwrap(cell, copy_get)]
+      struct Hits(Cell<u32>);
+    "#;
+    let mem_helpers = r#"
+      #[shrinkwrap(cell, mutable, mem_helpers)]
+      struct Hits(Cell<u32>);
+    "#;
+
+    let copy_get: syn::DeriveInput = syn::parse_str(copy_get).unwrap();
+    let mem_helpers: syn::DeriveInput = syn::parse_str(mem_helpers).unwrap();
+    let copy_get = validate_derive_input(copy_get).err().unwrap();
+    let mem_helpers = validate_derive_input(mem_helpers).err().unwrap();
+
+    assert!(copy_get.to_string().contains("combined with copy_get"));
+    assert!(mem_helpers.to_string().contains("combined with\nmem_helpers"));
+  }
+
+  #[test]
+  fn test_main_field_docs_kept() {
+    let input = r"
+      struct Mailbox {
+        /// The address, exactly as the user typed it.
+        #[shrinkwrap(main_field)]
+        addr: String,
+        /// Not this one.
+        name: String,
+      }
+    ";
+
+    let strct: syn::DeriveInput = syn::parse_str(input).unwrap();
+    let (_, input) = validate_derive_input(strct).unwrap();
+    let docs = input.inner_docs;
+
+    assert_eq!(docs.len(), 1);
+    assert!(quote!(#(#docs)*)
+      .to_string()
+      .contains("exactly as the user typed it"));
+  }
+}
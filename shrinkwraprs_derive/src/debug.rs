@@ -0,0 +1,70 @@
+//! With the `debug-expansions` feature, every derive also writes the code it
+//! generated to a file, so you can see what it did without `cargo expand`.
+//! Files go in `$SHRINKWRAP_DEBUG_DIR` if it's set, and in a `shrinkwraprs`
+//! directory under the system's temporary directory otherwise.
+
+use proc_macro2::TokenStream;
+
+#[cfg(feature = "debug-expansions")]
+pub fn dump(
+  ident: &syn::Ident,
+  derive: &str,
+  expanded: syn::Result<TokenStream>,
+) -> syn::Result<TokenStream> {
+  use std::env;
+  use std::fs;
+  use std::path::PathBuf;
+
+  let tokens = expanded?;
+
+  let dir = env::var_os("SHRINKWRAP_DEBUG_DIR")
+    .map(PathBuf::from)
+    .unwrap_or_else(|| env::temp_dir().join("shrinkwraprs"));
+  // Cargo tells rustc which crate it's building; keep wrappers with the same
+  // name in different crates from overwriting each other.
+  let krate = env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "crate".to_owned());
+  let path = dir.join(format!("{}-{}.{}.rs", krate, ident, derive));
+
+  let contents = format!(
+    "// #[derive({})] on `{}` in `{}`\n{}\n",
+    derive, ident, krate, tokens
+  );
+
+  fs::create_dir_all(&dir)
+    .and_then(|()| fs::write(&path, contents))
+    .map_err(|error| {
+      syn::Error::new_spanned(
+        ident,
+        format!(
+          "shrinkwraprs: couldn't write the expansion to {}: {}",
+          path.display(),
+          error
+        ),
+      )
+    })?;
+
+  note(ident, &path);
+  Ok(tokens)
+}
+
+#[cfg(not(feature = "debug-expansions"))]
+#[inline(always)]
+pub fn dump(
+  _ident: &syn::Ident,
+  _derive: &str,
+  expanded: syn::Result<TokenStream>,
+) -> syn::Result<TokenStream> {
+  expanded
+}
+
+/// Point at the file, if nightly lets us.
+#[cfg(all(feature = "debug-expansions", feature = "nightly-diagnostics"))]
+fn note(ident: &syn::Ident, path: &::std::path::Path) {
+  use proc_macro::{Diagnostic, Level};
+
+  let message = format!("shrinkwraprs: expansion written to {}", path.display());
+  Diagnostic::spanned(ident.span().unwrap(), Level::Note, message).emit();
+}
+
+#[cfg(all(feature = "debug-expansions", not(feature = "nightly-diagnostics")))]
+fn note(_ident: &syn::Ident, _path: &::std::path::Path) {}
@@ -0,0 +1,33 @@
+//! Some problems aren't worth failing the build over, but are still worth
+//! mentioning. Procedural macros can only emit warnings on nightly, so unless
+//! the `nightly-diagnostics` feature is enabled, warnings are quietly dropped.
+
+use proc_macro2::Span;
+
+/// Whether warnings actually make it to the user.
+pub const CAN_WARN: bool = cfg!(feature = "nightly-diagnostics");
+
+// Without the feature, nothing ever looks inside.
+#[cfg_attr(not(feature = "nightly-diagnostics"), allow(dead_code))]
+pub struct Warning {
+  pub span: Span,
+  pub message: String,
+}
+
+#[cfg(feature = "nightly-diagnostics")]
+pub fn emit(warnings: &[Warning]) {
+  use proc_macro::{Diagnostic, Level};
+
+  // Unit tests call the expansion functions directly, where there's no
+  // compiler to report to (and `Span::unwrap()` would panic).
+  if !proc_macro::is_available() {
+    return;
+  }
+
+  for Warning { span, message } in warnings {
+    Diagnostic::spanned(span.unwrap(), Level::Warning, message.as_str()).emit();
+  }
+}
+
+#[cfg(not(feature = "nightly-diagnostics"))]
+pub fn emit(_warnings: &[Warning]) {}
@@ -0,0 +1,3527 @@
+//! Derive macros for [`shrinkwraprs`](https://docs.rs/shrinkwraprs).
+//!
+//! You almost certainly want to depend on `shrinkwraprs` itself rather than
+//! on this crate directly; the code we generate refers to the traits defined
+//! there.
+
+// Additionally, perhaps subsume some functionality from
+// [`from_variants`](https://crates.io/crates/from_variants)?
+
+#![cfg_attr(feature = "strict", deny(warnings))]
+#![cfg_attr(feature = "nightly-diagnostics", feature(proc_macro_diagnostic))]
+#![recursion_limit = "128"]
+
+extern crate proc_macro;
+extern crate proc_macro2;
+extern crate syn;
+#[macro_use]
+extern crate quote;
+#[macro_use]
+extern crate bitflags;
+
+use proc_macro2::{Span, TokenStream};
+use quote::ToTokens;
+
+mod ast;
+mod debug;
+mod diagnostics;
+mod options;
+mod visibility;
+
+#[proc_macro_derive(Shrinkwrap, attributes(shrinkwrap))]
+pub fn shrinkwrap_derive(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let input = syn::parse_macro_input!(tokens as syn::DeriveInput);
+  let ident = input.ident.clone();
+
+  debug::dump(&ident, "Shrinkwrap", expand_shrinkwrap(input))
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+fn expand_shrinkwrap(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+  use crate::ast::{validate_derive_input, ShrinkwrapFlags};
+
+  let (details, input) = validate_derive_input(input)?;
+  diagnostics::emit(&details.warnings);
+
+  let mut items = TokenStream::new();
+  let mut tokens = TokenStream::new();
+
+  impl_field_ref(&details, &input).to_tokens(&mut tokens);
+
+  // The borrowed half of a pair wraps something unsized, so most of what we
+  // generate (which moves the inner value around) doesn't apply. The same
+  // goes for other structs around unsized values.
+  if details.unsized_inner {
+    impl_immut_borrows(&details, &input).to_tokens(&mut tokens);
+    impl_borrowed_half(&details, &input).to_tokens(&mut tokens);
+    if let Some(ref vis) = details.mut_access {
+      impl_mut_access(&details, &input, vis).to_tokens(&mut tokens);
+    }
+
+    return Ok(wrap_impls(tokens));
+  }
+
+  impl_shrinkwrap_trait(&details, &input).to_tokens(&mut tokens);
+  impl_immut_borrows(&details, &input).to_tokens(&mut tokens);
+  impl_accessors(&details, &input).to_tokens(&mut tokens);
+  if let Some(ref vis) = details.constructor {
+    impl_new(&details, &input, vis).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_WITH_FIELDS) {
+    impl_with_fields(&details, &input).to_tokens(&mut tokens);
+  }
+  if let Some(ref ext_trait) = details.ext_trait {
+    ext_trait_definition(&details, &input, ext_trait).to_tokens(&mut items);
+    impl_ext_trait(&details, &input, ext_trait).to_tokens(&mut tokens);
+  }
+  if !details.delegates.constructors.is_empty() {
+    impl_delegate_constructors(&details, &input).to_tokens(&mut tokens);
+  }
+  if !details.delegates.owned.is_empty() {
+    impl_delegate_owned(&details, &input).to_tokens(&mut tokens);
+  }
+  if !input.other_fields.is_empty() && !input.nested {
+    impl_parts(&details, &input).to_tokens(&mut tokens);
+  }
+  impl_map(&details, &input).to_tokens(&mut tokens);
+  if details.flags.contains(ShrinkwrapFlags::SW_OPT_RESULT) {
+    impl_option_result(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_OPTION) {
+    impl_option_inner(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_SLICE) {
+    impl_slice(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_LOCK) {
+    impl_lock(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_CELL) {
+    impl_cell(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_ATOMIC) {
+    impl_atomic(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_UUID) {
+    impl_uuid(&details, &input).to_tokens(&mut tokens);
+  }
+  if let Some(encoding) = details.display_encoding {
+    impl_display_encoding(&details, &input, encoding).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_ITER) {
+    impl_iter(&details, &input).to_tokens(&mut tokens);
+  }
+
+  if details.flags.contains(ShrinkwrapFlags::SW_COW) {
+    impl_cow(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_PATH_LIKE) {
+    impl_path_like(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_BYTES) {
+    impl_bytes(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_FWD_BORROW) {
+    impl_borrow_contract(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_EQUIVALENT) {
+    impl_equivalent(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_ZERO_COST) {
+    assert_zero_cost(&details, &input).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_SEND_SYNC) {
+    assert_send_sync(&details, &input).to_tokens(&mut tokens);
+  }
+  if let Some(ref borrowed) = details.borrowed {
+    impl_owned_half(&details, &input, borrowed).to_tokens(&mut tokens);
+  }
+  if details.flags.contains(ShrinkwrapFlags::SW_GEN_TESTS) {
+    gen_tests(&details, &input).to_tokens(&mut items);
+  }
+
+  if details.flags.contains(ShrinkwrapFlags::SW_TRANSPARENT) {
+    impl_transparent(&details, &input).to_tokens(&mut tokens);
+
+    // Reusing allocations needs the standard library's containers.
+    if ast::HAS_ALLOC {
+      impl_transparent_alloc(&details, &input).to_tokens(&mut tokens);
+    }
+  }
+
+  if details.flags.contains(ShrinkwrapFlags::SW_MUT) {
+    derive_mut(&details, &input)?.to_tokens(&mut tokens);
+  }
+  if let Some(ref vis) = details.mut_access {
+    impl_mut_access(&details, &input, vis).to_tokens(&mut tokens);
+  }
+
+  items.extend(wrap_impls(tokens));
+  Ok(items)
+}
+
+/// `#[shrinkwrap(..)]` on its own, without a `#[derive(Shrinkwrap)]`. It adds
+/// the derive, and since it can change the struct too, it also adds the
+/// `#[repr(transparent)]` that `transparent` needs if there's no `repr`. The
+/// options go back on the struct after the derive, where the derive reads
+/// them like usual.
+#[proc_macro_attribute]
+pub fn shrinkwrap(
+  args: proc_macro::TokenStream,
+  item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+  let tokens = TokenStream::from(args.clone());
+  let options = syn::parse_macro_input!(args as syn::AttributeArgs);
+  let item = syn::parse_macro_input!(item as syn::DeriveInput);
+
+  expand_shrinkwrap_attribute(&options, tokens, item)
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+fn expand_shrinkwrap_attribute(
+  options: &[syn::NestedMeta],
+  tokens: TokenStream,
+  mut item: syn::DeriveInput,
+) -> syn::Result<TokenStream> {
+  let transparent = options.iter().any(|option| match option {
+    syn::NestedMeta::Meta(syn::Meta::Path(path)) => path.is_ident("transparent"),
+    _ => false,
+  });
+  // With some other `repr`, the derive explains what's wrong better than
+  // rustc would about conflicting `repr`s.
+  let has_repr = item.attrs.iter().any(|attr| attr.path.is_ident("repr"));
+  if transparent && !has_repr {
+    item.attrs.push(syn::parse_quote!( #[repr(transparent)] ));
+  }
+
+  // Written above an existing `#[derive(Shrinkwrap)]`, this runs before the
+  // derive does, and just has to put the options back where it'll see them.
+  // First, so that any of the struct's own `#[shrinkwrap(..)]`s come after
+  // it.
+  if !derives_shrinkwrap(&item.attrs)? {
+    item.attrs.insert(0, syn::parse_quote!( #[derive(::shrinkwraprs::Shrinkwrap)] ));
+  }
+  if !options.is_empty() {
+    item.attrs.push(syn::parse_quote!( #[shrinkwrap(#tokens)] ));
+  }
+
+  Ok(quote!( #item ))
+}
+
+/// Declares a bunch of newtypes at once, each of them shrinkwrapped with the
+/// options in the `#![shrinkwrap(..)]` at the top.
+#[proc_macro]
+pub fn shrinkwrap_newtypes(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let newtypes = syn::parse_macro_input!(tokens as Newtypes);
+
+  expand_newtypes(newtypes)
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+struct Newtypes {
+  shared: Vec<syn::Attribute>,
+  items: Vec<syn::DeriveInput>,
+}
+
+impl syn::parse::Parse for Newtypes {
+  fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+    let shared = input.call(syn::Attribute::parse_inner)?;
+    let mut items = vec![];
+
+    while !input.is_empty() {
+      items.push(input.parse()?);
+    }
+
+    Ok(Newtypes { shared, items })
+  }
+}
+
+fn expand_newtypes(newtypes: Newtypes) -> syn::Result<TokenStream> {
+  let mut options = vec![];
+
+  for attr in &newtypes.shared {
+    match attr.parse_meta()? {
+      syn::Meta::List(syn::MetaList { ref path, nested, .. }) if path.is_ident("shrinkwrap") => {
+        options.extend(nested)
+      }
+      _ => {
+        return Err(syn::Error::new_spanned(
+          attr,
+          "shrinkwraprs: shrinkwrap_newtypes! only shares
+#![shrinkwrap(..)] between its structs. Put other
+attributes on the structs themselves.",
+        ))
+      }
+    }
+  }
+
+  let tokens = quote!( #(#options),* );
+  let mut expanded = TokenStream::new();
+
+  for item in newtypes.items {
+    expanded.extend(expand_shrinkwrap_attribute(&options, tokens.clone(), item)?);
+  }
+
+  Ok(expanded)
+}
+
+fn derives_shrinkwrap(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+  let mut derived = false;
+
+  for attr in attrs.iter().filter(|attr| attr.path.is_ident("derive")) {
+    let paths: syn::punctuated::Punctuated<syn::Path, syn::Token![,]> =
+      attr.parse_args_with(syn::punctuated::Punctuated::parse_terminated)?;
+
+    derived |= paths.iter().any(|path| match path.segments.last() {
+      Some(segment) => segment.ident == "Shrinkwrap",
+      None => false,
+    });
+  }
+
+  Ok(derived)
+}
+
+/// Derives just the mutable traits, for when you'd rather spell it
+/// `#[derive(Shrinkwrap, ShrinkwrapMut)]` than `#[shrinkwrap(mutable)]`.
+#[proc_macro_derive(ShrinkwrapMut, attributes(shrinkwrap))]
+pub fn shrinkwrap_mut(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
+  let input = syn::parse_macro_input!(tokens as syn::DeriveInput);
+  let ident = input.ident.clone();
+
+  debug::dump(&ident, "ShrinkwrapMut", expand_shrinkwrap_mut(input))
+    .unwrap_or_else(|error| error.to_compile_error())
+    .into()
+}
+
+fn expand_shrinkwrap_mut(input: syn::DeriveInput) -> syn::Result<TokenStream> {
+  use crate::ast::{validate_derive_input, ShrinkwrapFlags};
+
+  let (mut details, input) = validate_derive_input(input)?;
+
+  if details.flags.contains(ShrinkwrapFlags::SW_NO_MUT) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[derive(ShrinkwrapMut)] can't be used on a struct
+with #[shrinkwrap(main_field_mut = \"none\")], since there's no
+field to mutably convert to.",
+    ));
+  }
+
+  if details.flags.contains(ShrinkwrapFlags::SW_CHECKED) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[derive(ShrinkwrapMut)] can't be used on newtype!
+structs, since writing through `DerefMut` would skip the check.",
+    ));
+  }
+
+  if details.flags.contains(ShrinkwrapFlags::SW_MUT) {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[derive(ShrinkwrapMut)] already generates the
+mutable traits, so #[shrinkwrap(mutable)] would generate
+them twice. Use one or the other, but not both.",
+    ));
+  }
+
+  if details.unsized_inner {
+    return Err(syn::Error::new_spanned(
+      &details.ident,
+      "shrinkwraprs: #[derive(ShrinkwrapMut)] can't be used on a struct
+around an unsized value, like the borrowed half of a pair.",
+    ));
+  }
+
+  if let Some(ref vis) = details.mut_access {
+    return Err(syn::Error::new_spanned(
+      vis,
+      "shrinkwraprs: #[shrinkwrap(mut_access = \"..\")] is instead of the
+mutable traits, so it can't be combined with
+#[derive(ShrinkwrapMut)].",
+    ));
+  }
+
+  let check = check_shrinkwrap_derived(&details);
+  require_immut_traits(&mut details, &input);
+
+  let mut tokens = derive_mut(&details, &input)?;
+  tokens.extend(check);
+
+  Ok(wrap_impls(tokens))
+}
+
+/// Without `#[derive(Shrinkwrap)]`, the mutable traits are missing their
+/// supertraits, and rustc complains about each of them inside code the user
+/// never wrote. We can't reliably tell from the attributes whether the other
+/// derive is there (derives in the same list are stripped before we see
+/// them), so instead we make the mutable impls quietly not apply without
+/// the immutable ones, and check for `Shrinkwrap` on our own, pointing at the
+/// struct with a name that says what to do.
+fn require_immut_traits(details: &mut ast::StructDetails, input: &ast::Struct) {
+  let ident = &details.ident;
+  let inner_type = &input.inner_type;
+  let rust = rust_path(details);
+  let (_, ty_generics, _) = details.generics.split_for_impl();
+
+  // The bounds have to pin down the associated types too, or the mutable
+  // impls can't tell that they're the types we hand out.
+  let mut bounds: Vec<syn::WherePredicate> = vec![syn::parse_quote!(
+    for<'__shrinkwrap_a> #ident #ty_generics: ::shrinkwraprs::Shrinkwrap<Inner = #inner_type>
+  )];
+  if details.traits.contains(ast::Traits::DEREF_MUT) && details.borrowed.is_none() {
+    let target = if details.flags.contains(ast::ShrinkwrapFlags::SW_DEREF_VIA) {
+      quote!( <#inner_type as #rust::ops::Deref>::Target )
+    } else {
+      let target = details.deref_target.as_ref().unwrap_or(inner_type);
+
+      quote!( #target )
+    };
+
+    bounds.push(syn::parse_quote!(
+      for<'__shrinkwrap_a> #ident #ty_generics: #rust::ops::Deref<Target = #target>
+    ));
+  }
+  if details.traits.contains(ast::Traits::BORROW_MUT) {
+    bounds.push(syn::parse_quote!(
+      for<'__shrinkwrap_a> #ident #ty_generics: #rust::borrow::Borrow<#inner_type>
+    ));
+  }
+
+  details.generics.make_where_clause().predicates.extend(bounds);
+}
+
+fn check_shrinkwrap_derived(details: &ast::StructDetails) -> TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let rust = rust_path(details);
+  let check = quote_spanned!(ident.span()=>
+    derive_Shrinkwrap_along_with_ShrinkwrapMut::<#ident #ty_generics>()
+  );
+
+  quote! {
+    #[allow(dead_code, non_snake_case)]
+    fn __shrinkwrap_check #impl_generics () #where_clause {
+      fn derive_Shrinkwrap_along_with_ShrinkwrapMut<
+        __SHRINKWRAP_T: ?#rust::marker::Sized + ::shrinkwraprs::Shrinkwrap,
+      >() {
+      }
+
+      #check;
+    }
+  }
+}
+
+/// Impls don't need a name to take effect, so we put them all in an anonymous
+/// `const` block. That way nothing we generate can clash with, or be picked
+/// up by, the items around the struct.
+fn wrap_impls(impls: TokenStream) -> TokenStream {
+  quote! {
+    const _: () = {
+      #impls
+    };
+  }
+}
+
+fn derive_mut(details: &ast::StructDetails, input: &ast::Struct) -> syn::Result<TokenStream> {
+  use crate::visibility::field_visibility;
+  use crate::visibility::FieldVisibility::*;
+
+  // Make sure that the inner field isn't less visible than the outer struct.
+  if !details.flags.contains(ast::ShrinkwrapFlags::SW_IGNORE_VIS) {
+    match field_visibility(&details.visibility, &input.inner_visibility_mut) {
+      Restricted => {
+        return Err(syn::Error::new_spanned(
+          &details.ident,
+          "shrinkwraprs: cowardly refusing to implement mutable
+conversion traits because inner field is less visible
+than shrinkwrapped struct. Implementing mutable traits
+could allow violation of struct invariants. If you'd
+like to override this, use
+#[shrinkwrap(unsafe_ignore_visibility)] on your struct.",
+        ))
+      }
+      // We can't show that anything is wrong, so it's up to the user how
+      // much of a fuss to make.
+      CantDetermine => match details.visibility_policy {
+        ast::VisibilityPolicy::Allow => (),
+        ast::VisibilityPolicy::Warn => diagnostics::emit(&[diagnostics::Warning {
+          span: details.ident.span(),
+          message: "shrinkwraprs: can't figure out whether the inner field is as
+visible as the shrinkwrapped struct, so the mutable
+conversion traits might let code outside the struct's
+module break its invariants. If that's fine, use
+#[shrinkwrap(visibility_policy = \"allow\")] on your
+struct to silence this warning."
+            .to_owned(),
+        }]),
+        ast::VisibilityPolicy::Error => {
+          return Err(syn::Error::new_spanned(
+            &details.ident,
+            "shrinkwraprs: cowardly refusing to implement mutable
+conversion traits because I can't figure out whether
+the inner field is as visible as the shrinkwrapped
+struct or not. This is usually because there is a mix
+of visibilities starting at the crate root and
+visiblities starting at self/super. If you'd like to
+override this, use #[shrinkwrap(visibility_policy = \"allow\")]
+or #[shrinkwrap(unsafe_ignore_visibility)] on your struct.",
+          ))
+        }
+      },
+      _ => (),
+    }
+  }
+
+  let mut tokens = TokenStream::new();
+
+  impl_field_mut(details, input).to_tokens(&mut tokens);
+  impl_shrinkwrap_mut_trait(details, input).to_tokens(&mut tokens);
+  impl_mut_borrows(details, input).to_tokens(&mut tokens);
+  impl_mut_accessors(details, input).to_tokens(&mut tokens);
+  if details.flags.contains(ast::ShrinkwrapFlags::SW_BYTES) {
+    impl_bytes_mut(details, input).to_tokens(&mut tokens);
+  }
+
+  Ok(tokens)
+}
+
+/// The trait impls all reach the main field through one helper, rather than
+/// each spelling out the field access themselves. That's less code for the
+/// compiler to chew through in crates with lots of newtypes.
+fn impl_field_ref(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  // `#[inline(always)]` no matter what the struct asked for, since this is
+  // ours rather than part of the struct's API.
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #[doc(hidden)]
+      #[inline(always)]
+      fn __shrinkwrap_ref(&self) -> &#inner_type {
+        &self.#inner_field
+      }
+    }
+  }
+}
+
+/// `impl_field_ref()`, for the mutable traits.
+fn impl_field_mut(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field_mut: inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #[doc(hidden)]
+      #[inline(always)]
+      fn __shrinkwrap_mut(&mut self) -> &mut #inner_type {
+        &mut self.#inner_field
+      }
+    }
+  }
+}
+
+// When generating our code, we need to be careful not to leak things into the
+// surrounding code. For example, we don't use imports unless they're inside a
+// scope, because otherwise we'd be inserting invisible imports whenever a user
+// used #[derive(Shrinkwrap)].
+
+fn impl_shrinkwrap_trait(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let into_inner = quote::format_ident!("into_inner", span = input.inner_span);
+  let as_inner = quote::format_ident!("as_inner", span = input.inner_span);
+  let into_inner_doc = format!("Unwrap the `{}`, consuming the wrapper.", type_name(inner_type));
+  let as_inner_doc = format!("Borrow the wrapped `{}`.", type_name(inner_type));
+
+  quote! {
+    #[automatically_derived]
+    impl #impl_generics ::shrinkwraprs::Shrinkwrap for #ident #ty_generics #where_clause {
+      type Inner = #inner_type;
+
+      #[doc = #into_inner_doc]
+      fn #into_inner(self) -> Self::Inner {
+        self.#inner_field
+      }
+
+      #[doc = #as_inner_doc]
+      #inline
+      fn #as_inner(&self) -> &Self::Inner {
+        self.__shrinkwrap_ref()
+      }
+    }
+  }
+}
+
+fn impl_shrinkwrap_mut_trait(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let as_inner_mut = quote::format_ident!("as_inner_mut", span = input.inner_span_mut);
+  let doc = format!("Mutably borrow the wrapped `{}`.", type_name(&input.inner_type));
+
+  quote! {
+    #[automatically_derived]
+    impl #impl_generics ::shrinkwraprs::ShrinkwrapMut for #ident #ty_generics #where_clause {
+      #[doc = #doc]
+      #inline
+      fn #as_inner_mut(&mut self) -> &mut Self::Inner {
+        self.__shrinkwrap_mut()
+      }
+    }
+  }
+}
+
+fn impl_immut_borrows(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let inner_type = &input.inner_type;
+
+  let deref_cfg = cfg_gate(details, ast::Traits::DEREF);
+  let borrow_cfg = cfg_gate(details, ast::Traits::BORROW);
+  let as_ref_cfg = cfg_gate(details, ast::Traits::AS_REF);
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let rust = rust_path(details);
+  let inner_name = type_name(inner_type);
+  // The methods are named at the field, so "go to definition" lands there.
+  let deref_fn = quote::format_ident!("deref", span = input.inner_span);
+  let borrow_fn = quote::format_ident!("borrow", span = input.inner_span);
+  let as_ref_fn = quote::format_ident!("as_ref", span = input.inner_span);
+
+  let traits = details.traits;
+  let mut tokens = TokenStream::new();
+
+  if traits.contains(ast::Traits::DEREF) {
+    let (target, deref, doc) = if let Some(ref borrowed) = details.borrowed {
+      (
+        quote!( #borrowed ),
+        quote!( <#borrowed>::from_ref(#rust::ops::Deref::deref(self.__shrinkwrap_ref())) ),
+        format!("Dereference to the borrowed `{}`.", type_name(borrowed)),
+      )
+    } else if details.flags.contains(ast::ShrinkwrapFlags::SW_DEREF_VIA) {
+      (
+        quote!( <#inner_type as #rust::ops::Deref>::Target ),
+        quote!( &**self.__shrinkwrap_ref() ),
+        format!("Dereference through the wrapped `{}`.", inner_name),
+      )
+    } else {
+      let target = details.deref_target.as_ref().unwrap_or(inner_type);
+
+      (
+        quote!( #target ),
+        quote!( self.__shrinkwrap_ref() ),
+        format!("Dereference to the wrapped `{}`.", type_name(target)),
+      )
+    };
+
+    tokens.extend(quote! {
+      #deref_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::ops::Deref for #ident #ty_generics #where_clause {
+        type Target = #target;
+        #[doc = #doc]
+        #inline
+        fn #deref_fn(&self) -> &Self::Target {
+          #deref
+        }
+      }
+    });
+  }
+
+  let forward_borrow = details.flags.contains(ast::ShrinkwrapFlags::SW_FWD_BORROW);
+
+  if traits.contains(ast::Traits::BORROW) && forward_borrow {
+    let t = quote!(__SHRINKWRAP_T);
+    let mut forward_generics = generics.clone();
+    forward_generics.params.push(syn::parse_quote!( #t: ?#rust::marker::Sized ));
+    forward_generics
+      .make_where_clause()
+      .predicates
+      .push(syn::parse_quote!( #inner_type: #rust::borrow::Borrow<#t> ));
+    let (impl_generics, _, where_clause) = forward_generics.split_for_impl();
+    let doc = format!("Borrow anything that the wrapped `{}` can be borrowed as.", inner_name);
+
+    tokens.extend(quote! {
+      #borrow_cfg
+      #[allow(non_camel_case_types)]
+      #[automatically_derived]
+      impl #impl_generics #rust::borrow::Borrow<#t> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #borrow_fn(&self) -> &#t {
+          <#inner_type as #rust::borrow::Borrow<#t>>::borrow(self.__shrinkwrap_ref())
+        }
+      }
+    });
+  } else if traits.contains(ast::Traits::BORROW) {
+    let doc = format!("Borrow the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #borrow_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::borrow::Borrow<#inner_type> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #borrow_fn(&self) -> &#inner_type {
+          self.__shrinkwrap_ref()
+        }
+      }
+    });
+  }
+
+  let forward_as_ref = details.flags.contains(ast::ShrinkwrapFlags::SW_FWD_AS_REF);
+  // For smart pointers, like `Box<dyn Plugin>`, `AsRef` can go to what they
+  // point to instead.
+  let as_ref_via = details.flags.contains(ast::ShrinkwrapFlags::SW_AS_REF_VIA);
+  let deref_target = quote!( <#inner_type as #rust::ops::Deref>::Target );
+
+  if traits.contains(ast::Traits::AS_REF) && forward_as_ref {
+    let t = quote!(__SHRINKWRAP_T);
+    let mut forward_generics = generics.clone();
+    forward_generics.params.push(syn::parse_quote!( #t: ?#rust::marker::Sized ));
+    forward_generics
+      .make_where_clause()
+      .predicates
+      .push(syn::parse_quote!( #inner_type: #rust::convert::AsRef<#t> ));
+    let (impl_generics, _, where_clause) = forward_generics.split_for_impl();
+    let doc = format!("Get a reference to anything that the wrapped `{}` refers to.", inner_name);
+
+    tokens.extend(quote! {
+      #as_ref_cfg
+      #[allow(non_camel_case_types)]
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsRef<#t> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_ref_fn(&self) -> &#t {
+          <#inner_type as #rust::convert::AsRef<#t>>::as_ref(self.__shrinkwrap_ref())
+        }
+      }
+    });
+  } else if traits.contains(ast::Traits::AS_REF) && as_ref_via {
+    let doc = format!("Get a reference to what the wrapped `{}` points to.", inner_name);
+
+    tokens.extend(quote! {
+      #as_ref_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsRef<#deref_target> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_ref_fn(&self) -> &#deref_target {
+          &**self.__shrinkwrap_ref()
+        }
+      }
+    });
+  } else if traits.contains(ast::Traits::AS_REF) {
+    let doc = format!("Get a reference to the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #as_ref_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsRef<#inner_type> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_ref_fn(&self) -> &#inner_type {
+          self.__shrinkwrap_ref()
+        }
+      }
+    });
+  }
+
+  if traits.contains(ast::Traits::AS_REF) && details.flags.contains(ast::ShrinkwrapFlags::SW_SLICE) {
+    let slice = details.deref_target.as_ref().expect("halp! slice should set the deref target");
+    let doc = format!("Get a slice of the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #as_ref_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsRef<#slice> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_ref_fn(&self) -> &#slice {
+          self.__shrinkwrap_ref()
+        }
+      }
+    });
+  }
+
+  // With `transitive`, the main field is itself a shrinkwrapped newtype, and
+  // we can borrow straight through it to *its* inner value.
+  if details.flags.contains(ast::ShrinkwrapFlags::SW_TRANSITIVE) {
+    let innermost = quote!( <#inner_type as ::shrinkwraprs::Shrinkwrap>::Inner );
+
+    if traits.contains(ast::Traits::BORROW) {
+      let doc = format!("Borrow the value inside the wrapped `{}`.", inner_name);
+
+      tokens.extend(quote! {
+        #borrow_cfg
+        #[automatically_derived]
+        impl #impl_generics #rust::borrow::Borrow<#innermost> for #ident #ty_generics #where_clause {
+          #[doc = #doc]
+          #inline
+          fn #borrow_fn(&self) -> &#innermost {
+            ::shrinkwraprs::Shrinkwrap::as_inner(self.__shrinkwrap_ref())
+          }
+        }
+      });
+    }
+
+    if traits.contains(ast::Traits::AS_REF) {
+      let doc = format!("Get a reference to the value inside the wrapped `{}`.", inner_name);
+
+      tokens.extend(quote! {
+        #as_ref_cfg
+        #[automatically_derived]
+        impl #impl_generics #rust::convert::AsRef<#innermost> for #ident #ty_generics #where_clause {
+          #[doc = #doc]
+          #inline
+          fn #as_ref_fn(&self) -> &#innermost {
+            ::shrinkwraprs::Shrinkwrap::as_inner(self.__shrinkwrap_ref())
+          }
+        }
+      });
+    }
+  }
+
+  // Fields marked `#[shrinkwrap(borrow)]` are secondary views of the
+  // wrapper; `Deref` stays with the main field. With `as_ref_all`, every
+  // field whose type doesn't show up anywhere else gets an `AsRef` too.
+  let as_ref_all = details.flags.contains(ast::ShrinkwrapFlags::SW_AS_REF_ALL);
+  let type_count = |ty: &syn::Type| {
+    let ty = quote!(#ty).to_string();
+    let others = input.other_fields.iter().map(|other| &other.field.ty);
+
+    std::iter::once(inner_type)
+      .chain(others)
+      .filter(|other| quote!(#other).to_string() == ty)
+      .count()
+  };
+
+  for other in &input.other_fields {
+    let member = &other.member;
+    let ty = &other.field.ty;
+    let borrow = other.options.borrow;
+    let as_ref = !forward_as_ref && (borrow || (as_ref_all && type_count(ty) == 1));
+    let borrow_doc = format!("Borrow the `{}` field.", member);
+    let as_ref_doc = format!("Get a reference to the `{}` field.", member);
+    let span = ast::field_span(&other.field);
+    let borrow_fn = quote::format_ident!("borrow", span = span);
+    let as_ref_fn = quote::format_ident!("as_ref", span = span);
+
+    if borrow && traits.contains(ast::Traits::BORROW) {
+      tokens.extend(quote! {
+        #borrow_cfg
+        #[automatically_derived]
+        impl #impl_generics #rust::borrow::Borrow<#ty> for #ident #ty_generics #where_clause {
+          #[doc = #borrow_doc]
+          #inline
+          fn #borrow_fn(&self) -> &#ty {
+            &self.#member
+          }
+        }
+      });
+    }
+
+    if as_ref && traits.contains(ast::Traits::AS_REF) {
+      tokens.extend(quote! {
+        #as_ref_cfg
+        #[automatically_derived]
+        impl #impl_generics #rust::convert::AsRef<#ty> for #ident #ty_generics #where_clause {
+          #[doc = #as_ref_doc]
+          #inline
+          fn #as_ref_fn(&self) -> &#ty {
+            &self.#member
+          }
+        }
+      });
+    }
+  }
+
+  tokens
+}
+
+fn impl_mut_borrows(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let inner_type = &input.inner_type;
+
+  let deref_mut_cfg = cfg_gate(details, ast::Traits::DEREF_MUT);
+  let borrow_mut_cfg = cfg_gate(details, ast::Traits::BORROW_MUT);
+  let as_mut_cfg = cfg_gate(details, ast::Traits::AS_MUT);
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let rust = rust_path(details);
+  let inner_name = type_name(inner_type);
+  let deref_mut_fn = quote::format_ident!("deref_mut", span = input.inner_span_mut);
+  let borrow_mut_fn = quote::format_ident!("borrow_mut", span = input.inner_span_mut);
+  let as_mut_fn = quote::format_ident!("as_mut", span = input.inner_span_mut);
+
+  let traits = details.traits;
+  let mut tokens = TokenStream::new();
+
+  // The borrowed half of a pair is only ever handed out by shared reference,
+  // like `Path` is by `PathBuf`.
+  if traits.contains(ast::Traits::DEREF_MUT) && details.borrowed.is_none() {
+    let (deref_mut, doc) = if details.flags.contains(ast::ShrinkwrapFlags::SW_DEREF_VIA) {
+      (
+        quote!( &mut **self.__shrinkwrap_mut() ),
+        format!("Mutably dereference through the wrapped `{}`.", inner_name),
+      )
+    } else {
+      let target = details.deref_target.as_ref().unwrap_or(inner_type);
+
+      (
+        quote!( self.__shrinkwrap_mut() ),
+        format!("Mutably dereference to the wrapped `{}`.", type_name(target)),
+      )
+    };
+
+    tokens.extend(quote! {
+      #deref_mut_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::ops::DerefMut for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #deref_mut_fn(&mut self) -> &mut Self::Target {
+          #deref_mut
+        }
+      }
+    });
+  }
+
+  if traits.contains(ast::Traits::BORROW_MUT) {
+    let doc = format!("Mutably borrow the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #borrow_mut_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::borrow::BorrowMut<#inner_type> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #borrow_mut_fn(&mut self) -> &mut #inner_type {
+          self.__shrinkwrap_mut()
+        }
+      }
+    });
+  }
+
+  if traits.contains(ast::Traits::AS_MUT) && details.flags.contains(ast::ShrinkwrapFlags::SW_AS_REF_VIA) {
+    let target = quote!( <#inner_type as #rust::ops::Deref>::Target );
+    let doc = format!("Get a mutable reference to what the wrapped `{}` points to.", inner_name);
+
+    tokens.extend(quote! {
+      #as_mut_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsMut<#target> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_mut_fn(&mut self) -> &mut #target {
+          &mut **self.__shrinkwrap_mut()
+        }
+      }
+    });
+  } else if traits.contains(ast::Traits::AS_MUT) {
+    let doc = format!("Get a mutable reference to the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #as_mut_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsMut<#inner_type> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_mut_fn(&mut self) -> &mut #inner_type {
+          self.__shrinkwrap_mut()
+        }
+      }
+    });
+  }
+
+  if traits.contains(ast::Traits::AS_MUT) && details.flags.contains(ast::ShrinkwrapFlags::SW_SLICE) {
+    let slice = details.deref_target.as_ref().expect("halp! slice should set the deref target");
+    let doc = format!("Get a mutable slice of the wrapped `{}`.", inner_name);
+
+    tokens.extend(quote! {
+      #as_mut_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsMut<#slice> for #ident #ty_generics #where_clause {
+        #[doc = #doc]
+        #inline
+        fn #as_mut_fn(&mut self) -> &mut #slice {
+          self.__shrinkwrap_mut()
+        }
+      }
+    });
+  }
+
+  tokens
+}
+
+fn impl_accessors(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    names,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let into_inner = at_span(&names.into_inner, input.inner_span);
+  let as_inner = at_span(&names.as_inner, input.inner_span);
+  let get = &names.get;
+  let rust = rust_path(details);
+  let constness = constness(details);
+  // Moving the main field out drops whatever else is in the struct, which a
+  // const fn can't do if that might run a destructor.
+  let into_inner_constness = if input.nested
+    || input.other_fields.iter().any(|other| !ast::is_phantom_data(&other.field.ty))
+  {
+    TokenStream::new()
+  } else {
+    constness.clone()
+  };
+  let field_docs = field_docs(input);
+
+  // The `Shrinkwrap` trait already has these; the inherent versions are only
+  // there if asked for, since they'd clash with any the struct already has.
+  let accessors = if details.flags.contains(ast::ShrinkwrapFlags::SW_ACCESSORS) {
+    quote! {
+      /// Unwrap the wrapped value, consuming the wrapper in the process.
+      #field_docs
+      #must_use
+      pub #into_inner_constness fn #into_inner(self) -> #inner_type {
+        self.#inner_field
+      }
+
+      /// Get a reference to the wrapped value.
+      #field_docs
+      pub #constness fn #as_inner(&self) -> &#inner_type {
+        &self.#inner_field
+      }
+    }
+  } else {
+    TokenStream::new()
+  };
+
+  // Higher-ranked for the same reason as the bound on `take()`. Without any
+  // bound, a non-`Copy` inner type fails inside the generated body with a
+  // confusing "cannot move out of `self`".
+  let copy_get = if details.flags.contains(ast::ShrinkwrapFlags::SW_COPY_GET) {
+    quote! {
+      /// Get a copy of the wrapped value.
+      #must_use
+      pub #constness fn #get(&self) -> #inner_type
+        where for<'__shrinkwrap_a> #inner_type: #rust::marker::Copy
+      {
+        self.#inner_field
+      }
+    }
+  } else {
+    TokenStream::new()
+  };
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #accessors
+
+      #copy_get
+    }
+  }
+}
+
+/// With `#[shrinkwrap(cfg(.., traits(..)))]`, impls of the listed traits only
+/// exist when the given `cfg` predicate holds.
+fn cfg_gate(details: &ast::StructDetails, traits: ast::Traits) -> TokenStream {
+  let predicates = details
+    .cfgs
+    .iter()
+    .filter(|gate| gate.traits.intersects(traits))
+    .map(|gate| &gate.predicate)
+    .collect::<Vec<_>>();
+
+  if predicates.is_empty() {
+    TokenStream::new()
+  } else {
+    quote!( #[cfg(all(#(#predicates),*))] )
+  }
+}
+
+/// With `#[shrinkwrap(must_use)]`, constructors and conversions get marked
+/// `#[must_use]`, so that throwing away a freshly built wrapper is a warning.
+fn must_use(details: &ast::StructDetails) -> TokenStream {
+  if details.flags.contains(ast::ShrinkwrapFlags::SW_MUST_USE) {
+    quote!(#[must_use])
+  } else {
+    TokenStream::new()
+  }
+}
+
+/// Docs written on the main field carry over to the methods that stand in
+/// for it, after a blank line so they read as their own paragraph.
+fn field_docs(input: &ast::Struct) -> TokenStream {
+  let docs = &input.inner_docs;
+
+  if docs.is_empty() {
+    TokenStream::new()
+  } else {
+    quote!( #[doc = ""] #(#docs)* )
+  }
+}
+
+/// The accessors in the trait impls are one-liners on hot paths, so they get
+/// `#[inline]` unless asked otherwise with `#[shrinkwrap(inline(always))]` or
+/// `#[shrinkwrap(no_inline)]`.
+fn inline(details: &ast::StructDetails) -> TokenStream {
+  match details.inline {
+    ast::Inline::Hint => quote!(#[inline]),
+    ast::Inline::Always => quote!(#[inline(always)]),
+    ast::Inline::Omit => TokenStream::new(),
+  }
+}
+
+/// A copy of a (possibly renamed) method name that points at the field it
+/// stands in for.
+fn at_span(name: &syn::Ident, span: Span) -> syn::Ident {
+  let mut name = name.clone();
+  name.set_span(span);
+  name
+}
+
+/// Spell out a type for a generated doc comment or an error message. `quote`
+/// puts spaces between all of the tokens, which reads oddly.
+fn type_name<T: ToTokens>(ty: &T) -> String {
+  let spacing = [
+    (" :: ", "::"),
+    (":: ", "::"),
+    (" <", "<"),
+    ("< ", "<"),
+    (" >", ">"),
+    (" ,", ","),
+    ("& ", "&"),
+    ("[ ", "["),
+    (" ]", "]"),
+    ("( ", "("),
+    (" )", ")"),
+    (" ;", ";"),
+  ];
+
+  spacing
+    .iter()
+    .fold(quote!(#ty).to_string(), |name, &(from, to)| name.replace(from, to))
+}
+
+/// With `#[shrinkwrap(const_fns)]`, the simple constructors and accessors
+/// get marked `const`.
+fn constness(details: &ast::StructDetails) -> TokenStream {
+  if details.flags.contains(ast::ShrinkwrapFlags::SW_CONST_FNS) {
+    quote!(const)
+  } else {
+    TokenStream::new()
+  }
+}
+
+/// Trait impls are as visible as the traits themselves, so mutable access
+/// that's meant to stay within the crate has to be an inherent method.
+fn impl_mut_access(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  vis: &syn::Visibility,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field_mut: inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let inner_mut = at_span(&details.names.inner_mut, input.inner_span_mut);
+  let doc = format!("Mutably borrow the wrapped `{}`.", type_name(inner_type));
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #[doc = #doc]
+      #inline
+      #vis fn #inner_mut(&mut self) -> &mut #inner_type {
+        &mut self.#inner_field
+      }
+    }
+  }
+}
+
+fn impl_mut_accessors(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field_mut: inner_field,
+    inner_type,
+    inner_visibility_mut: inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let rust = rust_path(details);
+  let as_inner_mut = at_span(&details.names.as_inner_mut, input.inner_span_mut);
+  let ast::MethodNames {
+    set,
+    replace,
+    take,
+    swap,
+    ..
+  } = &details.names;
+
+  // These would hide the inner type's own `replace()`, `take()` and so on
+  // behind `DerefMut`, so they're only there if asked for.
+  //
+  // The bound on `take()` is higher-ranked so that it isn't a "trivial" bound
+  // when the inner type is concrete; otherwise the compiler would reject it
+  // outright for inner types that aren't `Default`, instead of just making
+  // `take()` uncallable.
+  let as_inner_mut = if details.flags.contains(ast::ShrinkwrapFlags::SW_ACCESSORS) {
+    quote! {
+      /// Get a mutable reference to the wrapped value.
+      #inner_visibility fn #as_inner_mut(&mut self) -> &mut #inner_type {
+        &mut self.#inner_field
+      }
+    }
+  } else {
+    TokenStream::new()
+  };
+  let mem_helpers = if details.flags.contains(ast::ShrinkwrapFlags::SW_MEM_HELPERS) {
+    quote! {
+      /// Set the wrapped value, dropping the old one.
+      #inner_visibility fn #set(&mut self, new: #inner_type) {
+        self.#inner_field = new;
+      }
+
+      /// Replace the wrapped value, returning the old one.
+      #inner_visibility fn #replace(&mut self, new: #inner_type) -> #inner_type {
+        #rust::mem::replace(&mut self.#inner_field, new)
+      }
+
+      /// Take the wrapped value, leaving its default value in its place.
+      #inner_visibility fn #take(&mut self) -> #inner_type
+        where for<'__shrinkwrap_a> #inner_type: #rust::default::Default
+      {
+        #rust::mem::replace(&mut self.#inner_field, #rust::default::Default::default())
+      }
+
+      /// Swap the wrapped values of two wrappers, leaving any other fields
+      /// alone.
+      #inner_visibility fn #swap(&mut self, other: &mut Self) {
+        #rust::mem::swap(&mut self.#inner_field, &mut other.#inner_field)
+      }
+    }
+  } else {
+    TokenStream::new()
+  };
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #as_inner_mut
+
+      #mem_helpers
+    }
+  }
+}
+
+fn impl_with_fields(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  // There's nothing useful to set on a `PhantomData`.
+  let setters = input
+    .other_fields
+    .iter()
+    .filter(|other| !ast::is_phantom_data(&other.field.ty))
+    .map(|other| {
+      let syn::Field { ident, ty, vis, .. } = &other.field;
+      let ident = ident.as_ref().unwrap();
+      let name = other.options.rename.as_ref().unwrap_or(ident);
+      let with = quote::format_ident!("with_{}", name);
+
+      quote! {
+        /// Set this field, builder-style.
+        #must_use
+        #vis fn #with(mut self, value: #ty) -> Self {
+          self.#ident = value;
+          self
+        }
+      }
+    });
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #(#setters)*
+    }
+  }
+}
+
+fn impl_new(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  vis: &syn::Visibility,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    other_fields,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let constness = constness(details);
+  let new_name = &details.names.new;
+  let field_docs = field_docs(input);
+  let others = other_fields.iter().map(|other| {
+    let member = &other.member;
+
+    if ast::is_phantom_data(&other.field.ty) {
+      quote!( #member: #rust::marker::PhantomData )
+    } else {
+      quote!( #member: #rust::default::Default::default() )
+    }
+  });
+
+  let wrapped = quote! {
+    #ident {
+      #inner_field: inner,
+      #(#others),*
+    }
+  };
+
+  let new = if details.flags.contains(ast::ShrinkwrapFlags::SW_CTOR_INTO) {
+    let i = quote!(__SHRINKWRAP_I);
+
+    quote! {
+      /// Wrap anything that converts into the wrapped type, filling in any
+      /// other fields with their defaults.
+      #field_docs
+      #must_use
+      #vis fn #new_name<#i: #rust::convert::Into<#inner_type>>(inner: #i) -> Self {
+        let inner = inner.into();
+        #wrapped
+      }
+    }
+  } else {
+    quote! {
+      /// Wrap a value, filling in any other fields with their defaults.
+      #field_docs
+      #must_use
+      #vis #constness fn #new_name(inner: #inner_type) -> Self {
+        #wrapped
+      }
+    }
+  };
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #new
+    }
+  }
+}
+
+/// Moves every field out of the wrapper at once, as a tuple in declaration
+/// order, either with `into_parts()` or with `.into()`. Only makes sense
+/// when there's more than just the main field.
+fn impl_parts(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_index,
+    other_fields,
+    ..
+  } = input;
+
+  let mut members: Vec<_> = other_fields
+    .iter()
+    .map(|other| other.member.clone())
+    .collect();
+  let mut types: Vec<_> = other_fields
+    .iter()
+    .map(|other| other.field.ty.clone())
+    .collect();
+  members.insert(*inner_index, inner_field.clone());
+  types.insert(*inner_index, inner_type.clone());
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let into_parts = &details.names.into_parts;
+
+  // Handing out every field is only as safe as the least visible one. A
+  // trait impl can't be made less visible, so `From` is only there if every
+  // field is as visible as the struct.
+  let field_vises = std::iter::once(&input.inner_visibility)
+    .chain(other_fields.iter().map(|other| &other.field.vis))
+    .collect::<Vec<_>>();
+  let vis = visibility::least_visible(&details.visibility, field_vises.iter().cloned());
+  let all_visible = field_vises.iter().all(|field_vis| {
+    matches!(
+      visibility::field_visibility(&details.visibility, field_vis),
+      visibility::FieldVisibility::Visible
+    )
+  });
+
+  let from_impl = if all_visible {
+    quote! {
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::From<#ident #ty_generics> for ( #(#types),* )
+        #where_clause
+      {
+        /// Unwrap every field at once.
+        fn from(wrapper: #ident #ty_generics) -> Self {
+          wrapper.#into_parts()
+        }
+      }
+    }
+  } else {
+    TokenStream::new()
+  };
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Unwrap every field at once, consuming the wrapper in the process.
+      #must_use
+      #vis fn #into_parts(self) -> ( #(#types),* ) {
+        ( #(self.#members),* )
+      }
+    }
+
+    #from_impl
+  }
+}
+
+fn impl_map(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let ast::MethodNames {
+    map,
+    map_ref,
+    map_mut,
+    transform,
+    transform_ref,
+    ..
+  } = &details.names;
+
+  // This is a *massive* hack to avoid variable capture, but I can't figure out
+  // how to get `quote` to enforce hygiene or generate a gensym.
+  let f = quote!(__SHRINKWRAP_F);
+  let t = quote!(__SHRINKWRAP_T);
+
+  // Without consuming the original, we can only build a new wrapper if
+  // there's nothing else in it that we'd need to clone.
+  let transform_ref = construct_from_inner(details, input, &quote!(inner)).map(|wrapped| {
+    quote! {
+      /// Build a new wrapper out of the result of a function applied to the
+      /// wrapped value.
+      #must_use
+      #inner_visibility fn #transform_ref<#f>(&self, f: #f) -> Self
+        where #f: #rust::ops::FnOnce(&#inner_type) -> #inner_type
+      {
+        let inner = f(&self.#inner_field);
+        #wrapped
+      }
+    }
+  });
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Map a function over the wrapped value, consuming it in the process.
+      #must_use
+      pub fn #map<#t, #f: #rust::ops::FnMut(#inner_type) -> #t>(self, mut f: #f) -> #t {
+        f(self.#inner_field)
+      }
+
+      /// Map a function over the wrapped value without consuming it.
+      #must_use
+      pub fn #map_ref<#t, #f: #rust::ops::FnMut(&#inner_type) -> #t>(&self, mut f: #f) -> #t {
+        f(&self.#inner_field)
+      }
+
+      /// Map a function over the wrapped value, potentially changing it in place.
+      #inner_visibility fn #map_mut<#t, #f>(&mut self, mut f: #f) -> #t
+        where #f: #rust::ops::FnMut(&mut #inner_type) -> #t
+      {
+        f(&mut self.#inner_field)
+      }
+
+      /// Replace the wrapped value with the result of a function applied to
+      /// it, staying inside the wrapper.
+      #must_use
+      #inner_visibility fn #transform<#f>(self, f: #f) -> Self
+        where #f: #rust::ops::FnOnce(#inner_type) -> #inner_type
+      {
+        let mut wrapped = self;
+        wrapped.#inner_field = f(wrapped.#inner_field);
+        wrapped
+      }
+
+      #transform_ref
+    }
+  }
+}
+
+fn impl_option_result(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    visibility,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let e = quote!(__SHRINKWRAP_E);
+  let ast::MethodNames {
+    wrap_opt,
+    wrap_result,
+    unwrap_opt,
+    unwrap_result,
+    ..
+  } = &details.names;
+
+  // Wrapping needs us to be able to construct the struct; unwrapping gives
+  // the same access as `map()`, so it gets the visibility of the struct.
+  let wrap = construct_from_inner(details, input, &quote!(inner)).map(|wrapped| {
+    quote! {
+      /// Wrap the value inside an `Option`, if there is one.
+      #must_use
+      #inner_visibility fn #wrap_opt(inner: #rust::option::Option<#inner_type>) -> #rust::option::Option<Self> {
+        inner.map(|inner| #wrapped)
+      }
+
+      /// Wrap the success value inside a `Result`, if there is one.
+      #must_use
+      #inner_visibility fn #wrap_result<#e>(
+        inner: #rust::result::Result<#inner_type, #e>
+      ) -> #rust::result::Result<Self, #e> {
+        inner.map(|inner| #wrapped)
+      }
+    }
+  });
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #wrap
+
+      /// Unwrap the wrapper inside an `Option`, if there is one.
+      #must_use
+      #visibility fn #unwrap_opt(wrapped: #rust::option::Option<Self>) -> #rust::option::Option<#inner_type> {
+        wrapped.map(|wrapped| wrapped.#inner_field)
+      }
+
+      /// Unwrap the wrapper inside a successful `Result`, if there is one.
+      #must_use
+      #visibility fn #unwrap_result<#e>(
+        wrapped: #rust::result::Result<Self, #e>
+      ) -> #rust::result::Result<#inner_type, #e> {
+        wrapped.map(|wrapped| wrapped.#inner_field)
+      }
+    }
+  }
+}
+
+/// With `#[shrinkwrap(option)]`, the usual `Option` methods that only read
+/// the value, so they don't need dereferencing first. Like `map()`, they
+/// have the visibility of the struct.
+fn impl_option_inner(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    visibility,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let t = ast::option_inner(inner_type).expect("halp! the main field should be an Option");
+  let f = quote!(__SHRINKWRAP_F);
+
+  // The bounds on the individual methods are higher-ranked for the same
+  // reason as the one on `take()`.
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Whether there's a value inside.
+      #visibility fn is_some(&self) -> bool {
+        self.#inner_field.is_some()
+      }
+
+      /// Whether there's no value inside.
+      #visibility fn is_none(&self) -> bool {
+        self.#inner_field.is_none()
+      }
+
+      /// Borrow the value inside, if there is one.
+      #visibility fn as_opt(&self) -> #rust::option::Option<&#t> {
+        self.#inner_field.as_ref()
+      }
+
+      /// Borrow what the value inside derefs to, if there is one.
+      #visibility fn as_deref(&self) -> #rust::option::Option<&<#t as #rust::ops::Deref>::Target>
+        where for<'__shrinkwrap_a> #t: #rust::ops::Deref
+      {
+        self.#inner_field.as_deref()
+      }
+
+      /// Unwrap the value inside, or use `default` if there isn't one.
+      #must_use
+      #visibility fn unwrap_or(self, default: #t) -> #t {
+        self.#inner_field.unwrap_or(default)
+      }
+
+      /// Unwrap the value inside, or compute one if there isn't one.
+      #must_use
+      #visibility fn unwrap_or_else<#f: #rust::ops::FnOnce() -> #t>(self, f: #f) -> #t {
+        self.#inner_field.unwrap_or_else(f)
+      }
+
+      /// Unwrap the value inside, or use the default if there isn't one.
+      #must_use
+      #visibility fn unwrap_or_default(self) -> #t
+        where for<'__shrinkwrap_a> #t: #rust::default::Default
+      {
+        self.#inner_field.unwrap_or_default()
+      }
+    }
+  }
+}
+
+/// With `#[shrinkwrap(slice)]`, `as_slice()` and `as_mut_slice()` like on
+/// `Vec`. The mutable one has the visibility of the inner field, like
+/// `map_mut()`.
+fn impl_slice(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let slice = details.deref_target.as_ref().expect("halp! slice should set the deref target");
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Get the wrapped array as a slice.
+      #inline
+      pub fn as_slice(&self) -> &#slice {
+        &self.#inner_field
+      }
+
+      /// Get the wrapped array as a mutable slice.
+      #inline
+      #inner_visibility fn as_mut_slice(&mut self) -> &mut #slice {
+        &mut self.#inner_field
+      }
+    }
+  }
+}
+
+/// With `#[shrinkwrap(lock)]`, `lock()` for a `Mutex`, or `read()` and
+/// `write()` for an `RwLock`. Anything that can change the value inside has
+/// the visibility of the inner field, like `map_mut()`.
+fn impl_lock(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let a = quote!('__shrinkwrap_a);
+  let parking_lot = quote!( ::shrinkwraprs::__private::parking_lot );
+  let rust = rust_path(details);
+  let std = std_path(details);
+  // `parking_lot`'s locks can't be poisoned, so its guards come back as is.
+  let guard = |name: &str, t: &syn::Type| {
+    let name = syn::Ident::new(name, Span::call_site());
+
+    match details.lock_backend {
+      ast::LockBackend::Std => quote!( #std::sync::LockResult<#std::sync::#name<#a, #t>> ),
+      ast::LockBackend::ParkingLot => quote!( #parking_lot::#name<#a, #t> ),
+    }
+  };
+  let is_parking_lot = details.lock_backend == ast::LockBackend::ParkingLot;
+  let u = quote!(__SHRINKWRAP_U);
+  let f = quote!(__SHRINKWRAP_F);
+
+  let methods = match ast::lock_inner(inner_type).expect("halp! the main field should be a lock") {
+    ast::Lock::Mutex(t) => {
+      let guard = guard("MutexGuard", t);
+      // Only `parking_lot`'s guards can be narrowed down to part of the
+      // value.
+      let mapped = if is_parking_lot {
+        quote! {
+          /// Lock the wrapped mutex, and keep only part of the value
+          /// locked in the guard.
+          #inline
+          #inner_visibility fn lock_mapped<#a, #u: ?Sized, #f>(
+            &#a self,
+            f: #f,
+          ) -> #parking_lot::MappedMutexGuard<#a, #u>
+            where #f: #rust::ops::FnOnce(&mut #t) -> &mut #u
+          {
+            #parking_lot::MutexGuard::map(self.#inner_field.lock(), f)
+          }
+        }
+      } else {
+        TokenStream::new()
+      };
+
+      quote! {
+        /// Lock the wrapped mutex, blocking until it's free.
+        #inline
+        #inner_visibility fn lock<#a>(&#a self) -> #guard {
+          self.#inner_field.lock()
+        }
+
+        #mapped
+      }
+    }
+    ast::Lock::RwLock(t) => {
+      let read_guard = guard("RwLockReadGuard", t);
+      let write_guard = guard("RwLockWriteGuard", t);
+      let mapped = if is_parking_lot {
+        quote! {
+          /// Lock the wrapped lock for reading, and keep only part of the
+          /// value locked in the guard.
+          #inline
+          pub fn read_mapped<#a, #u: ?Sized, #f>(
+            &#a self,
+            f: #f,
+          ) -> #parking_lot::MappedRwLockReadGuard<#a, #u>
+            where #f: #rust::ops::FnOnce(&#t) -> &#u
+          {
+            #parking_lot::RwLockReadGuard::map(self.#inner_field.read(), f)
+          }
+
+          /// Lock the wrapped lock for writing, and keep only part of the
+          /// value locked in the guard.
+          #inline
+          #inner_visibility fn write_mapped<#a, #u: ?Sized, #f>(
+            &#a self,
+            f: #f,
+          ) -> #parking_lot::MappedRwLockWriteGuard<#a, #u>
+            where #f: #rust::ops::FnOnce(&mut #t) -> &mut #u
+          {
+            #parking_lot::RwLockWriteGuard::map(self.#inner_field.write(), f)
+          }
+        }
+      } else {
+        TokenStream::new()
+      };
+
+      quote! {
+        /// Lock the wrapped lock for reading, blocking until there are no
+        /// writers.
+        #inline
+        pub fn read<#a>(&#a self) -> #read_guard {
+          self.#inner_field.read()
+        }
+
+        /// Lock the wrapped lock for writing, blocking until it's free.
+        #inline
+        #inner_visibility fn write<#a>(&#a self) -> #write_guard {
+          self.#inner_field.write()
+        }
+
+        #mapped
+      }
+    }
+  };
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #methods
+    }
+  }
+}
+
+/// With `#[shrinkwrap(cell)]`, the same for a `Cell`'s `get()`, `set()`,
+/// `replace()` and `update()`, or a `RefCell`'s `borrow()`, `try_borrow()`
+/// and `borrow_mut()`.
+fn impl_cell(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let rust = rust_path(details);
+  let a = quote!('__shrinkwrap_a);
+
+  let f = quote!(__SHRINKWRAP_F);
+  let ast::MethodNames {
+    get,
+    set,
+    replace,
+    update,
+    borrow,
+    try_borrow,
+    borrow_mut,
+    ..
+  } = &details.names;
+
+  // `Cell::update()` is too new to call, so it's spelled out. The `Copy`
+  // bounds are higher-ranked for the same reason as the one on `take()`.
+  let methods = match ast::cell_inner(inner_type).expect("halp! the main field should be a cell") {
+    ast::Cell::Cell(t) => quote! {
+      /// Get a copy of the value in the wrapped `Cell`.
+      #inline
+      pub fn #get(&self) -> #t
+        where for<'__shrinkwrap_a> #t: #rust::marker::Copy
+      {
+        self.#inner_field.get()
+      }
+
+      /// Change the value in the wrapped `Cell`.
+      #inline
+      #inner_visibility fn #set(&self, new: #t) {
+        self.#inner_field.set(new)
+      }
+
+      /// Change the value in the wrapped `Cell`, returning the old one.
+      #inline
+      #inner_visibility fn #replace(&self, new: #t) -> #t {
+        self.#inner_field.replace(new)
+      }
+
+      /// Change the value in the wrapped `Cell` with a function, returning
+      /// the new one.
+      #inline
+      #inner_visibility fn #update<#f: #rust::ops::FnOnce(#t) -> #t>(&self, f: #f) -> #t
+        where for<'__shrinkwrap_a> #t: #rust::marker::Copy
+      {
+        let new = f(self.#inner_field.get());
+        self.#inner_field.set(new);
+        new
+      }
+    },
+    ast::Cell::RefCell(t) => quote! {
+      /// Borrow the value in the wrapped `RefCell`, panicking if it's
+      /// mutably borrowed.
+      #inline
+      pub fn #borrow<#a>(&#a self) -> #rust::cell::Ref<#a, #t> {
+        self.#inner_field.borrow()
+      }
+
+      /// Borrow the value in the wrapped `RefCell`, unless it's mutably
+      /// borrowed.
+      #inline
+      pub fn #try_borrow<#a>(
+        &#a self,
+      ) -> #rust::result::Result<#rust::cell::Ref<#a, #t>, #rust::cell::BorrowError> {
+        self.#inner_field.try_borrow()
+      }
+
+      /// Mutably borrow the value in the wrapped `RefCell`, panicking if
+      /// it's borrowed at all.
+      #inline
+      #inner_visibility fn #borrow_mut<#a>(&#a self) -> #rust::cell::RefMut<#a, #t> {
+        self.#inner_field.borrow_mut()
+      }
+    },
+  };
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #methods
+    }
+  }
+}
+
+/// With `#[shrinkwrap(atomic)]`, `load()` and `store()` for an atomic, plus
+/// `fetch_add()` and `fetch_sub()` if it holds an integer.
+fn impl_atomic(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let rust = rust_path(details);
+  let value = ast::atomic_value(inner_type).expect("halp! the main field should be an atomic");
+  let ordering = quote!( #rust::sync::atomic::Ordering );
+
+  let arithmetic = if value == "bool" {
+    TokenStream::new()
+  } else {
+    quote! {
+      /// Add to the wrapped value, wrapping around on overflow, and return
+      /// the old one.
+      #inline
+      #inner_visibility fn fetch_add(&self, value: #value, order: #ordering) -> #value {
+        self.#inner_field.fetch_add(value, order)
+      }
+
+      /// Subtract from the wrapped value, wrapping around on overflow, and
+      /// return the old one.
+      #inline
+      #inner_visibility fn fetch_sub(&self, value: #value, order: #ordering) -> #value {
+        self.#inner_field.fetch_sub(value, order)
+      }
+    }
+  };
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Load the wrapped value.
+      #inline
+      pub fn load(&self, order: #ordering) -> #value {
+        self.#inner_field.load(order)
+      }
+
+      /// Store a new wrapped value.
+      #inline
+      #inner_visibility fn store(&self, value: #value, order: #ordering) {
+        self.#inner_field.store(value, order)
+      }
+
+      #arithmetic
+    }
+  }
+}
+
+/// With `#[shrinkwrap(uuid)]`, the `Uuid` constructors people reach for,
+/// returning the wrapper. Like the `wrap_*()` methods, they have the
+/// visibility of the inner field.
+fn impl_uuid(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  let wrapped = construct_from_inner(details, input, &quote!(inner))
+    .expect("halp! uuid should have checked the other fields");
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Wrap a new random (version 4) UUID.
+      #must_use
+      #inner_visibility fn new_v4() -> Self {
+        let inner = <#inner_type>::new_v4();
+        #wrapped
+      }
+
+      /// Wrap the nil UUID, with all bits set to zero.
+      #must_use
+      #inner_visibility fn nil() -> Self {
+        let inner = <#inner_type>::nil();
+        #wrapped
+      }
+
+      /// Parse and wrap a UUID in any of the formats `Uuid::parse_str()`
+      /// takes.
+      #inner_visibility fn parse_str(
+        input: &str,
+      ) -> #rust::result::Result<Self, ::shrinkwraprs::uuid::Error> {
+        let inner = <#inner_type>::parse_str(input)?;
+        #rust::result::Result::Ok(#wrapped)
+      }
+
+      /// The bytes of the wrapped UUID.
+      #must_use
+      pub fn as_bytes(&self) -> &[u8; 16] {
+        self.#inner_field.as_bytes()
+      }
+    }
+  }
+}
+
+/// With `#[shrinkwrap(display_encoding = "..")]`, `Display` and `FromStr`
+/// that spell the bytes out in that encoding, through the helpers in
+/// `shrinkwraprs::__private`.
+fn impl_display_encoding(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  encoding: ast::Encoding,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let rust = rust_path(details);
+  let private = quote!(::shrinkwraprs::__private);
+  let encoding = match encoding {
+    ast::Encoding::Hex => quote!( #private::Encoding::Hex ),
+    ast::Encoding::Base58 => quote!( #private::Encoding::Base58 ),
+    ast::Encoding::Base64 => quote!( #private::Encoding::Base64 ),
+  };
+  let wrapped = construct_from_inner(details, input, &quote!(inner))
+    .expect("halp! display_encoding should have checked the other fields");
+
+  // Arrays have to come out exactly full, which `ArrayOutput` checks.
+  let parse = if ast::is_byte_vec(inner_type) {
+    quote! {
+      let mut inner = <#inner_type>::new();
+      #private::decode(s, #encoding, &mut inner)?;
+    }
+  } else {
+    quote! {
+      let mut output = #private::ArrayOutput::new();
+      #private::decode(s, #encoding, &mut output)?;
+      let inner = output.finish()?;
+    }
+  };
+
+  quote! {
+    #[automatically_derived]
+    impl #impl_generics #rust::fmt::Display for #ident #ty_generics #where_clause {
+      fn fmt(&self, f: &mut #rust::fmt::Formatter) -> #rust::fmt::Result {
+        #private::encode(&self.#inner_field[..], #encoding, f)
+      }
+    }
+
+    #[automatically_derived]
+    impl #impl_generics #rust::str::FromStr for #ident #ty_generics #where_clause {
+      type Err = ::shrinkwraprs::DecodeError;
+
+      fn from_str(s: &str) -> #rust::result::Result<Self, Self::Err> {
+        #parse
+        #rust::result::Result::Ok(#wrapped)
+      }
+    }
+  }
+}
+
+fn impl_iter(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    visibility,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let rust = rust_path(details);
+  let i = quote!(__SHRINKWRAP_I);
+  let ast::MethodNames {
+    wrap_iter,
+    unwrap_iter,
+    ..
+  } = &details.names;
+
+  let wrap = construct_from_inner(details, input, &quote!(inner)).map(|wrapped| {
+    quote! {
+      /// Wrap every item of an iterator over the wrapped type.
+      #inner_visibility fn #wrap_iter<#i>(inner: #i) -> impl #rust::iter::Iterator<Item = Self>
+        where #i: #rust::iter::IntoIterator<Item = #inner_type>
+      {
+        let inner = #rust::iter::IntoIterator::into_iter(inner);
+        #rust::iter::Iterator::map(inner, |inner| #wrapped)
+      }
+    }
+  });
+
+  quote! {
+    #[allow(dead_code, non_camel_case_types)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #wrap
+
+      /// Unwrap every item of an iterator over wrappers.
+      #visibility fn #unwrap_iter<#i>(wrapped: #i) -> impl #rust::iter::Iterator<Item = #inner_type>
+        where #i: #rust::iter::IntoIterator<Item = Self>
+      {
+        let wrapped = #rust::iter::IntoIterator::into_iter(wrapped);
+        #rust::iter::Iterator::map(wrapped, |wrapped| wrapped.#inner_field)
+      }
+    }
+  }
+}
+
+/// Build an expression constructing the struct out of just a value for the
+/// main field, if that's possible -- that is, if all the other fields are
+/// `PhantomData`s that we can fill in ourselves.
+fn construct_from_inner(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  value: &TokenStream,
+) -> Option<TokenStream> {
+  let ident = &details.ident;
+  let inner_field = &input.inner_field;
+  let rust = rust_path(details);
+
+  let mut others = vec![];
+  for other in &input.other_fields {
+    if !ast::is_phantom_data(&other.field.ty) {
+      return None;
+    }
+    let member = &other.member;
+    others.push(quote!( #member: #rust::marker::PhantomData ));
+  }
+
+  Some(quote! {
+    #ident { #inner_field: #value, #(#others),* }
+  })
+}
+
+fn impl_delegate_constructors(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    delegates,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let wrapped = construct_from_inner(details, input, &quote!(inner)).unwrap();
+  let constructors = delegates.constructors.iter().map(|sig| {
+    let syn::Signature {
+      ident,
+      generics,
+      inputs,
+      ..
+    } = sig;
+    let fn_where_clause = &generics.where_clause;
+    let args = ast::delegate_args(sig).expect("halp! delegated arguments should have been checked");
+    let doc = format!("Wrap the result of `{}()` on the wrapped type.", ident);
+
+    quote! {
+      #[doc = #doc]
+      #must_use
+      #inner_visibility fn #ident #generics (#inputs) -> Self #fn_where_clause {
+        let inner = <#inner_type>::#ident( #(#args),* );
+        #wrapped
+      }
+    }
+  });
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #(#constructors)*
+    }
+  }
+}
+
+/// Methods that return the inner type get their result rewrapped, so that
+/// e.g. a `String` wrapper's `into_boxed_str()` still returns a
+/// `Box<str>` but a hypothetical `trimmed(self) -> Self` keeps the wrapper.
+fn impl_delegate_owned(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    delegates,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let methods = delegates.owned.iter().map(|sig| {
+    let syn::Signature {
+      ident,
+      generics,
+      inputs,
+      output,
+      ..
+    } = sig;
+    let fn_where_clause = &generics.where_clause;
+    let args = ast::delegate_args(sig).expect("halp! delegated arguments should have been checked");
+    let call = quote!( self.#inner_field.#ident( #(#args),* ) );
+    let doc = format!("Unwrap the wrapped value and call `{}()` on it.", ident);
+
+    if ast::returns_inner(sig, inner_type) {
+      let wrapped = construct_from_inner(details, input, &quote!(inner)).unwrap();
+
+      quote! {
+        #[doc = #doc]
+        #must_use
+        #inner_visibility fn #ident #generics (#inputs) -> Self #fn_where_clause {
+          let inner = #call;
+          #wrapped
+        }
+      }
+    } else {
+      quote! {
+        #[doc = #doc]
+        pub fn #ident #generics (#inputs) #output #fn_where_clause {
+          #call
+        }
+      }
+    }
+  });
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      #(#methods)*
+    }
+  }
+}
+
+/// The trait carries all of the wrapper's generics, since the inner type
+/// might not mention all of them. Unlike everything else we generate, users
+/// need to be able to name it, so it goes outside of `wrap_impls()`.
+fn ext_trait_definition(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  ext_trait: &ast::ExtTrait,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::ExtTrait { name, method } = ext_trait;
+
+  let (_, ty_generics, where_clause) = generics.split_for_impl();
+  let inner_visibility = &input.inner_visibility;
+  let doc = format!("Wrap values in a `{}`.", ident);
+
+  quote! {
+    #[doc = #doc]
+    #inner_visibility trait #name #generics #where_clause {
+      /// Wrap this value.
+      fn #method(self) -> #ident #ty_generics;
+    }
+  }
+}
+
+fn impl_ext_trait(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  ext_trait: &ast::ExtTrait,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_type,
+    ..
+  } = input;
+  let ast::ExtTrait { name, method } = ext_trait;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let wrapped = construct_from_inner(details, input, &quote!(self)).unwrap();
+
+  quote! {
+    #[automatically_derived]
+    impl #impl_generics #name #ty_generics for #inner_type #where_clause {
+      fn #method(self) -> #ident #ty_generics {
+        #wrapped
+      }
+    }
+  }
+}
+
+/// `Borrow` promises that borrowed values compare and hash the same as the
+/// originals. With `forward_borrow` handing out everything the inner value
+/// borrows as, the only way to keep that promise is to compare and hash
+/// exactly like the inner value does.
+fn impl_borrow_contract(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let rust = rust_path(details);
+  let h = quote!(__SHRINKWRAP_H);
+
+  let mut eq_generics = generics.clone();
+  eq_generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!( #inner_type: #rust::cmp::Eq + #rust::hash::Hash ));
+  let (impl_generics, ty_generics, where_clause) = eq_generics.split_for_impl();
+
+  // Not every key type is ordered, so the ordering impls only show up when
+  // the inner type has them, using the same trick as `take()`.
+  let mut ord_generics = eq_generics.clone();
+  ord_generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!( for<'__shrinkwrap_a> #inner_type: #rust::cmp::Ord ));
+  let (ord_impl_generics, _, ord_where_clause) = ord_generics.split_for_impl();
+
+  quote! {
+    #[automatically_derived]
+    impl #impl_generics #rust::cmp::PartialEq for #ident #ty_generics #where_clause {
+      /// Compare the wrapped values, consistently with `Borrow`.
+      fn eq(&self, other: &Self) -> bool {
+        <#inner_type as #rust::cmp::PartialEq>::eq(&self.#inner_field, &other.#inner_field)
+      }
+    }
+
+    #[automatically_derived]
+    impl #impl_generics #rust::cmp::Eq for #ident #ty_generics #where_clause {}
+
+    #[automatically_derived]
+    impl #impl_generics #rust::hash::Hash for #ident #ty_generics #where_clause {
+      /// Hash the wrapped value, consistently with `Borrow`.
+      #[allow(non_camel_case_types)]
+      fn hash<#h: #rust::hash::Hasher>(&self, state: &mut #h) {
+        <#inner_type as #rust::hash::Hash>::hash(&self.#inner_field, state)
+      }
+    }
+
+    #[automatically_derived]
+    impl #ord_impl_generics #rust::cmp::PartialOrd for #ident #ty_generics #ord_where_clause {
+      /// Order by the wrapped values, consistently with `Borrow`.
+      fn partial_cmp(&self, other: &Self) -> #rust::option::Option<#rust::cmp::Ordering> {
+        #rust::option::Option::Some(#rust::cmp::Ord::cmp(self, other))
+      }
+    }
+
+    #[automatically_derived]
+    impl #ord_impl_generics #rust::cmp::Ord for #ident #ty_generics #ord_where_clause {
+      /// Order by the wrapped values, consistently with `Borrow`.
+      fn cmp(&self, other: &Self) -> #rust::cmp::Ordering {
+        <#inner_type as #rust::cmp::Ord>::cmp(&self.#inner_field, &other.#inner_field)
+      }
+    }
+  }
+}
+
+/// The borrowed half of a `Path`/`PathBuf`-style pair, which the owned half
+/// casts its contents into, or any other struct around an unsized value.
+fn impl_borrowed_half(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let inner_field = &input.inner_field;
+
+  // The borrowed half of a pair is only ever handed out by shared reference,
+  // like `Path` is by `PathBuf`; on its own, a struct can be cast from
+  // mutable references too. Only the borrowed half needs to tell the owned
+  // half how it's put together.
+  let (from_mut, half) = if details.owned.is_some() {
+    let half = quote! {
+      #[automatically_derived]
+      #[doc(hidden)]
+      impl #impl_generics ::shrinkwraprs::__private::BorrowedHalf for #ident #ty_generics #where_clause {
+        type Inner = #inner_type;
+
+        #inline
+        fn as_inner(&self) -> &#inner_type {
+          &self.#inner_field
+        }
+
+        fn from_inner(inner: &#inner_type) -> &Self {
+          // Sound because the struct is `#[repr(transparent)]` over the inner type.
+          unsafe { &*(inner as *const #inner_type as *const Self) }
+        }
+      }
+    };
+
+    (TokenStream::new(), half)
+  } else {
+    let from_mut = quote! {
+      /// Reinterpret a mutable reference to the wrapped value as a mutable
+      /// reference to the wrapper.
+      #inner_visibility fn from_mut(inner: &mut #inner_type) -> &mut Self {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(inner as *mut #inner_type as *mut Self) }
+      }
+    };
+
+    (from_mut, TokenStream::new())
+  };
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Reinterpret a reference to the wrapped value as a reference to the wrapper.
+      #inner_visibility fn from_ref(inner: &#inner_type) -> &Self {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(inner as *const #inner_type as *const Self) }
+      }
+
+      #from_mut
+    }
+
+    #half
+  }
+}
+
+/// The owned half of a pair, which borrows as and derefs to the borrowed
+/// half, and is what the borrowed half turns into with `to_owned()`.
+fn impl_owned_half(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+  borrowed: &syn::Type,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    ..
+  } = input;
+
+  let rust = rust_path(details);
+  let alloc = alloc_path(details);
+  let borrow_cfg = cfg_gate(details, ast::Traits::BORROW);
+  let as_ref_cfg = cfg_gate(details, ast::Traits::AS_REF);
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let traits = details.traits;
+
+  // `ToOwned` is implemented on the borrowed half, so it only gets the
+  // generics that the borrowed half mentions. Anything else gets its
+  // default, which validation made sure is there.
+  let mut to_owned_generics = generics.clone();
+  to_owned_generics.params = generics
+    .params
+    .iter()
+    .filter(|param| ast::mentions(borrowed, param))
+    .cloned()
+    .collect();
+  if let Some(where_clause) = &mut to_owned_generics.where_clause {
+    where_clause.predicates = where_clause
+      .predicates
+      .iter()
+      .filter(|predicate| {
+        generics
+          .params
+          .iter()
+          .all(|param| ast::mentions(borrowed, param) || !ast::mentions(predicate, param))
+      })
+      .cloned()
+      .collect();
+  }
+  let (to_owned_impl_generics, _, to_owned_where_clause) = to_owned_generics.split_for_impl();
+  let owned_args = generics.params.iter().map(|param| match param {
+    syn::GenericParam::Type(syn::TypeParam { default: Some(default), .. })
+      if !ast::mentions(borrowed, param) =>
+    {
+      quote!(#default)
+    }
+    syn::GenericParam::Const(syn::ConstParam { default: Some(default), .. })
+      if !ast::mentions(borrowed, param) =>
+    {
+      quote!({ #default })
+    }
+    syn::GenericParam::Type(syn::TypeParam { ident, .. }) => quote!(#ident),
+    syn::GenericParam::Lifetime(syn::LifetimeDef { lifetime, .. }) => quote!(#lifetime),
+    syn::GenericParam::Const(syn::ConstParam { ident, .. }) => quote!(#ident),
+  });
+  let owned = if generics.params.is_empty() {
+    quote!(#ident)
+  } else {
+    quote!( #ident<#(#owned_args),*> )
+  };
+
+  let half = quote!( <#borrowed as ::shrinkwraprs::__private::BorrowedHalf> );
+  let view = quote!( #half::from_inner(#rust::ops::Deref::deref(&self.#inner_field)) );
+  let wrapped = construct_from_inner(
+    details,
+    input,
+    &quote!( #alloc::borrow::ToOwned::to_owned(#half::as_inner(self)) ),
+  )
+  .expect("halp! the owned half of a pair should always be constructible");
+  let borrow_doc = format!("Borrow as a `{}`.", type_name(borrowed));
+  let to_owned_doc = format!("Copy into a new `{}`.", ident);
+  let as_ref_doc = format!("Get a reference to the contents as a `{}`.", type_name(borrowed));
+  let mut tokens = TokenStream::new();
+
+  if traits.contains(ast::Traits::BORROW) {
+    tokens.extend(quote! {
+      #borrow_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::borrow::Borrow<#borrowed> for #ident #ty_generics #where_clause {
+        #[doc = #borrow_doc]
+        #inline
+        fn borrow(&self) -> &#borrowed {
+          #view
+        }
+      }
+
+      #borrow_cfg
+      #[automatically_derived]
+      impl #to_owned_impl_generics #alloc::borrow::ToOwned for #borrowed #to_owned_where_clause {
+        type Owned = #owned;
+
+        #[doc = #to_owned_doc]
+        fn to_owned(&self) -> #owned {
+          #wrapped
+        }
+      }
+    });
+  }
+
+  if traits.contains(ast::Traits::AS_REF) {
+    tokens.extend(quote! {
+      #as_ref_cfg
+      #[automatically_derived]
+      impl #impl_generics #rust::convert::AsRef<#borrowed> for #ident #ty_generics #where_clause {
+        #[doc = #as_ref_doc]
+        #inline
+        fn as_ref(&self) -> &#borrowed {
+          #view
+        }
+      }
+    });
+  }
+
+  tokens
+}
+
+/// `hashbrown` and `indexmap` look keys up through `Equivalent`, which comes
+/// for free with `Borrow`. This fills in the lookups that `Borrow` doesn't
+/// already cover.
+fn impl_equivalent(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let rust = rust_path(details);
+  let borrows = details.traits.contains(ast::Traits::BORROW);
+  let mut tokens = TokenStream::new();
+
+  if !borrows {
+    tokens.extend(quote! {
+      #[automatically_derived]
+      impl #impl_generics ::shrinkwraprs::equivalent::Equivalent<#ident #ty_generics>
+        for #inner_type #where_clause
+      {
+        /// Compare with the value wrapped by `key`.
+        fn equivalent(&self, key: &#ident #ty_generics) -> bool {
+          <#inner_type as #rust::cmp::PartialEq>::eq(self, &key.#inner_field)
+        }
+      }
+    });
+  }
+
+  // `String` keys are usually looked up by `str`, which needs `Borrow<str>`.
+  let is_string = quote!(#inner_type).to_string() == "String";
+  let forwards = borrows && details.flags.contains(ast::ShrinkwrapFlags::SW_FWD_BORROW);
+
+  if is_string && !forwards {
+    tokens.extend(quote! {
+      #[automatically_derived]
+      impl #impl_generics ::shrinkwraprs::equivalent::Equivalent<#ident #ty_generics>
+        for str #where_clause
+      {
+        /// Compare with the value wrapped by `key`.
+        fn equivalent(&self, key: &#ident #ty_generics) -> bool {
+          self == key.#inner_field.as_str()
+        }
+      }
+    });
+  }
+
+  tokens
+}
+
+/// `#[shrinkwrap(assert_zero_cost)]` turns a wrapper that's grown bigger or
+/// more aligned than its inner value into a compile error.
+fn assert_zero_cost(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ident = &details.ident;
+  let inner_type = &input.inner_type;
+  let rust = rust_path(details);
+  let inner_name = type_name(inner_type);
+
+  let size_message = format!(
+    "shrinkwraprs: `{}` isn't the same size as the `{}` it wraps",
+    ident, inner_name
+  );
+  let align_message = format!(
+    "shrinkwraprs: `{}` isn't aligned the same as the `{}` it wraps",
+    ident, inner_name
+  );
+
+  // Spanned at the struct, so that's where a failed assertion points.
+  quote_spanned! {ident.span()=>
+    const _: () = {
+      assert!(
+        #rust::mem::size_of::<#ident>() == #rust::mem::size_of::<#inner_type>(),
+        #size_message
+      );
+      assert!(
+        #rust::mem::align_of::<#ident>() == #rust::mem::align_of::<#inner_type>(),
+        #align_message
+      );
+    };
+  }
+}
+
+/// `#[shrinkwrap(assert_send_sync)]` makes sure the wrapper doesn't lose
+/// `Send` or `Sync` on account of its other fields. Each check assumes that
+/// the inner type has the auto trait, so it applies to generic wrappers too,
+/// and doesn't complain about inner types that never had it.
+fn assert_send_sync(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ident = &details.ident;
+  let inner_type = &input.inner_type;
+  let rust = rust_path(details);
+  let (_, ty_generics, _) = details.generics.split_for_impl();
+
+  let check = |name: &str, auto_trait: TokenStream| {
+    let check_fn = quote::format_ident!("__shrinkwrap_assert_{}", name);
+    let assertion = quote::format_ident!("{}_wrapper_when_inner_is_{}", name, name);
+    let mut generics = details.generics.clone();
+    generics
+      .make_where_clause()
+      .predicates
+      .push(syn::parse_quote!( for<'__shrinkwrap_a> #inner_type: #auto_trait ));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let call = quote_spanned!(ident.span()=> #assertion::<#ident #ty_generics>());
+
+    quote! {
+      #[allow(dead_code)]
+      fn #check_fn #impl_generics () #where_clause {
+        fn #assertion<__SHRINKWRAP_T: ?#rust::marker::Sized + #auto_trait>() {}
+
+        #call;
+      }
+    }
+  };
+
+  let mut tokens = check("send", quote!( #rust::marker::Send ));
+  tokens.extend(check("sync", quote!( #rust::marker::Sync )));
+  tokens
+}
+
+/// `#[shrinkwrap(gen_tests)]` emits a test module next to the struct that
+/// wraps a sample value and checks it comes back out of each conversion
+/// unchanged. The module can't go in the anonymous `const` block with the
+/// impls, since the test harness wouldn't find it there.
+fn gen_tests(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    other_fields,
+    ..
+  } = input;
+
+  let rust = rust_path(details);
+  let traits = details.traits;
+  let flags = details.flags;
+  let module = quote::format_ident!("__shrinkwrap_tests_{}", ident);
+  let sample = match details.test_sample {
+    Some(ref sample) => quote!( #sample ),
+    None => quote!( <#inner_type as #rust::default::Default>::default() ),
+  };
+  let others = other_fields.iter().map(|other| {
+    let member = &other.member;
+
+    if ast::is_phantom_data(&other.field.ty) {
+      quote!( #member: #rust::marker::PhantomData )
+    } else {
+      quote!( #member: #rust::default::Default::default() )
+    }
+  });
+
+  let mut tests = quote! {
+    fn sample() -> #inner_type {
+      #sample
+    }
+
+    fn wrapped() -> #ident {
+      #ident {
+        #inner_field: sample(),
+        #(#others),*
+      }
+    }
+
+    #[test]
+    fn into_inner_round_trips() {
+      assert!(::shrinkwraprs::Shrinkwrap::into_inner(wrapped()) == sample());
+    }
+
+    #[test]
+    fn as_inner_round_trips() {
+      assert!(*::shrinkwraprs::Shrinkwrap::as_inner(&wrapped()) == sample());
+    }
+  };
+
+  let plain_deref = details.deref_target.is_none()
+    && details.borrowed.is_none()
+    && !flags.contains(ast::ShrinkwrapFlags::SW_DEREF_VIA);
+  if traits.contains(ast::Traits::DEREF) && plain_deref {
+    let cfg = cfg_gate(details, ast::Traits::DEREF);
+
+    tests.extend(quote! {
+      #cfg
+      #[test]
+      fn deref_round_trips() {
+        assert!(*#rust::ops::Deref::deref(&wrapped()) == sample());
+      }
+    });
+  }
+  if traits.contains(ast::Traits::BORROW) {
+    let cfg = cfg_gate(details, ast::Traits::BORROW);
+
+    tests.extend(quote! {
+      #cfg
+      #[test]
+      fn borrow_round_trips() {
+        let wrapped = wrapped();
+        let borrowed: &#inner_type = #rust::borrow::Borrow::borrow(&wrapped);
+        assert!(*borrowed == sample());
+      }
+    });
+  }
+  // `AsRef` forwarded to the inner type, or to what it points to, doesn't
+  // include the inner type itself.
+  let as_ref_inner = !flags.intersects(ast::ShrinkwrapFlags::SW_FWD_AS_REF | ast::ShrinkwrapFlags::SW_AS_REF_VIA);
+  if traits.contains(ast::Traits::AS_REF) && as_ref_inner {
+    let cfg = cfg_gate(details, ast::Traits::AS_REF);
+
+    tests.extend(quote! {
+      #cfg
+      #[test]
+      fn as_ref_round_trips() {
+        let wrapped = wrapped();
+        let referenced: &#inner_type = #rust::convert::AsRef::as_ref(&wrapped);
+        assert!(*referenced == sample());
+      }
+    });
+  }
+  // Only if the mutable traits point at the main field too; otherwise there's
+  // no telling what to put in.
+  let same_field = inner_field.to_string() == input.inner_field_mut.to_string();
+  if flags.contains(ast::ShrinkwrapFlags::SW_MUT) && same_field {
+    tests.extend(quote! {
+      #[test]
+      fn as_inner_mut_round_trips() {
+        let mut wrapped = wrapped();
+        *::shrinkwraprs::ShrinkwrapMut::as_inner_mut(&mut wrapped) = sample();
+        assert!(::shrinkwraprs::Shrinkwrap::into_inner(wrapped) == sample());
+      }
+    });
+  }
+  if flags.contains(ast::ShrinkwrapFlags::SW_TRANSPARENT) {
+    tests.extend(quote! {
+      #[test]
+      fn from_ref_round_trips() {
+        let sample = sample();
+        let wrapped = #ident::from_ref(&sample);
+        assert!(*::shrinkwraprs::Shrinkwrap::as_inner(wrapped) == sample);
+      }
+    });
+  }
+
+  quote! {
+    #[cfg(test)]
+    #[allow(non_snake_case)]
+    mod #module {
+      use super::*;
+
+      #tests
+    }
+  }
+}
+
+/// Lets path-ish wrappers go straight into `std::fs` and `std::process`.
+fn impl_path_like(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let std = std_path(details);
+  let as_ref_cfg = cfg_gate(details, ast::Traits::AS_REF);
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+
+  quote! {
+    #as_ref_cfg
+    #[automatically_derived]
+    impl #impl_generics #std::convert::AsRef<#std::path::Path> for #ident #ty_generics #where_clause {
+      /// View the wrapped value as a `Path`.
+      #inline
+      fn as_ref(&self) -> &#std::path::Path {
+        <#inner_type as #std::convert::AsRef<#std::path::Path>>::as_ref(&self.#inner_field)
+      }
+    }
+
+    #as_ref_cfg
+    #[automatically_derived]
+    impl #impl_generics #std::convert::AsRef<#std::ffi::OsStr> for #ident #ty_generics #where_clause {
+      /// View the wrapped value as an `OsStr`.
+      #inline
+      fn as_ref(&self) -> &#std::ffi::OsStr {
+        <#inner_type as #std::convert::AsRef<#std::ffi::OsStr>>::as_ref(&self.#inner_field)
+      }
+    }
+  }
+}
+
+/// Lets byte-ish wrappers go straight into hashing and I/O APIs.
+fn impl_bytes(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let as_ref_cfg = cfg_gate(details, ast::Traits::AS_REF);
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let inline = inline(details);
+  let rust = rust_path(details);
+
+  quote! {
+    #as_ref_cfg
+    #[automatically_derived]
+    impl #impl_generics #rust::convert::AsRef<[u8]> for #ident #ty_generics #where_clause {
+      /// View the wrapped value as bytes.
+      #inline
+      fn as_ref(&self) -> &[u8] {
+        <#inner_type as #rust::convert::AsRef<[u8]>>::as_ref(&self.#inner_field)
+      }
+    }
+  }
+}
+
+fn impl_bytes_mut(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field_mut: inner_field,
+    inner_type,
+    ..
+  } = input;
+
+  let as_mut_cfg = cfg_gate(details, ast::Traits::AS_MUT);
+
+  let rust = rust_path(details);
+
+  // `String` has no `AsMut<[u8]>`, since that would let you break its UTF-8
+  // invariant. Bounding on it (with the same trick as `take()`) means
+  // `String` wrappers still compile; they just don't get the impl.
+  let mut bytes_generics = generics.clone();
+  bytes_generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!( for<'__shrinkwrap_a> #inner_type: #rust::convert::AsMut<[u8]> ));
+  let (impl_generics, ty_generics, where_clause) = bytes_generics.split_for_impl();
+  let inline = inline(details);
+
+  quote! {
+    #as_mut_cfg
+    #[automatically_derived]
+    impl #impl_generics #rust::convert::AsMut<[u8]> for #ident #ty_generics #where_clause {
+      /// View the wrapped value as mutable bytes.
+      #inline
+      fn as_mut(&mut self) -> &mut [u8] {
+        <#inner_type as #rust::convert::AsMut<[u8]>>::as_mut(&mut self.#inner_field)
+      }
+    }
+  }
+}
+
+fn impl_cow(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let rust = rust_path(details);
+  let alloc = alloc_path(details);
+  let (_, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+
+  // The `From` impls need an extra lifetime for the `Cow`, and all of this
+  // needs the inner type to be `Clone` so that it's `ToOwned`.
+  let lifetime = syn::Lifetime::new("'__shrinkwrap_a", Span::call_site());
+  let mut cow_generics = generics.clone();
+  cow_generics.params.insert(
+    0,
+    syn::GenericParam::Lifetime(syn::LifetimeDef::new(lifetime.clone())),
+  );
+  cow_generics
+    .make_where_clause()
+    .predicates
+    .push(syn::parse_quote!( #inner_type: #rust::clone::Clone ));
+  let (cow_impl_generics, _, cow_where_clause) = cow_generics.split_for_impl();
+
+  let inner = quote!(inner);
+  let from_cow = construct_from_inner(details, input, &quote!( #inner.into_owned() )).map(|wrapped| {
+    let (impl_generics, _, _) = generics.split_for_impl();
+
+    quote! {
+      #[allow(dead_code)]
+      #[automatically_derived]
+      impl #impl_generics #ident #ty_generics #where_clause {
+        /// Wrap a possibly-borrowed value of the wrapped type, cloning it if
+        /// necessary.
+        #must_use
+        #inner_visibility fn from_cow(#inner: #alloc::borrow::Cow<#inner_type>) -> Self
+          where #inner_type: #rust::clone::Clone
+        {
+          #wrapped
+        }
+      }
+    }
+  });
+
+  quote! {
+    #[automatically_derived]
+    impl #cow_impl_generics #rust::convert::From<#ident #ty_generics>
+      for #alloc::borrow::Cow<#lifetime, #inner_type> #cow_where_clause
+    {
+      /// Unwrap into an owned `Cow`.
+      fn from(wrapped: #ident #ty_generics) -> Self {
+        #alloc::borrow::Cow::Owned(wrapped.#inner_field)
+      }
+    }
+
+    #[automatically_derived]
+    impl #cow_impl_generics #rust::convert::From<&#lifetime #ident #ty_generics>
+      for #alloc::borrow::Cow<#lifetime, #inner_type> #cow_where_clause
+    {
+      /// Borrow the wrapped value as a `Cow`.
+      fn from(wrapped: &#lifetime #ident #ty_generics) -> Self {
+        #alloc::borrow::Cow::Borrowed(&wrapped.#inner_field)
+      }
+    }
+
+    #from_cow
+  }
+}
+
+fn impl_transparent(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_field,
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+  let rust = rust_path(details);
+  // Same hack as in `impl_map()`, so we don't capture a user's `N`.
+  let n = quote!(__SHRINKWRAP_N);
+
+  // Creating a wrapper out of an arbitrary inner value is just as powerful as
+  // being able to construct it directly, so those get the same visibility
+  // as the inner field. Same goes for handing out mutable access to the
+  // inner values, as with `map_mut()`.
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Reinterpret a reference to the wrapped type as a reference to the wrapper.
+      #inner_visibility fn from_ref(inner: &#inner_type) -> &Self {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(inner as *const #inner_type as *const Self) }
+      }
+
+      /// Reinterpret a mutable reference to the wrapped type as a mutable
+      /// reference to the wrapper.
+      #inner_visibility fn from_mut(inner: &mut #inner_type) -> &mut Self {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(inner as *mut #inner_type as *mut Self) }
+      }
+
+      /// Get a raw pointer to the wrapped value, which is also a valid pointer
+      /// to the wrapper.
+      pub fn as_ptr(&self) -> *const #inner_type {
+        &self.#inner_field as *const #inner_type
+      }
+
+      /// Get a mutable raw pointer to the wrapped value, which is also a valid
+      /// pointer to the wrapper.
+      #inner_visibility fn as_mut_ptr(&mut self) -> *mut #inner_type {
+        &mut self.#inner_field as *mut #inner_type
+      }
+
+      /// Reinterpret a slice of the wrapped type as a slice of wrappers.
+      #inner_visibility fn from_slice(inner: &[#inner_type]) -> &[Self] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(inner as *const [#inner_type] as *const [Self]) }
+      }
+
+      /// Reinterpret a mutable slice of the wrapped type as a mutable slice
+      /// of wrappers.
+      #inner_visibility fn from_slice_mut(inner: &mut [#inner_type]) -> &mut [Self] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(inner as *mut [#inner_type] as *mut [Self]) }
+      }
+
+      /// Reinterpret a slice of wrappers as a slice of the wrapped type.
+      pub fn as_inner_slice(wrapped: &[Self]) -> &[#inner_type] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(wrapped as *const [Self] as *const [#inner_type]) }
+      }
+
+      /// Reinterpret a mutable slice of wrappers as a mutable slice of the
+      /// wrapped type.
+      #inner_visibility fn as_inner_slice_mut(wrapped: &mut [Self]) -> &mut [#inner_type] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(wrapped as *mut [Self] as *mut [#inner_type]) }
+      }
+
+      /// Turn an array of the wrapped type into an array of wrappers.
+      #must_use
+      #inner_visibility fn wrap_array<const #n: usize>(inner: [#inner_type; #n]) -> [Self; #n] {
+        let inner = #rust::mem::ManuallyDrop::new(inner);
+
+        // Sound because the struct is `#[repr(transparent)]` over the inner
+        // type, and we've made sure the original array doesn't get dropped.
+        unsafe { #rust::ptr::read(&*inner as *const [#inner_type; #n] as *const [Self; #n]) }
+      }
+
+      /// Turn an array of wrappers into an array of the wrapped type.
+      #must_use
+      pub fn unwrap_array<const #n: usize>(wrapped: [Self; #n]) -> [#inner_type; #n] {
+        let wrapped = #rust::mem::ManuallyDrop::new(wrapped);
+
+        // Sound because the struct is `#[repr(transparent)]` over the inner
+        // type, and we've made sure the original array doesn't get dropped.
+        unsafe { #rust::ptr::read(&*wrapped as *const [Self; #n] as *const [#inner_type; #n]) }
+      }
+
+      /// Reinterpret a reference to an array of the wrapped type as a
+      /// reference to an array of wrappers.
+      #inner_visibility fn from_array_ref<const #n: usize>(inner: &[#inner_type; #n]) -> &[Self; #n] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(inner as *const [#inner_type; #n] as *const [Self; #n]) }
+      }
+
+      /// Reinterpret a mutable reference to an array of the wrapped type as a
+      /// mutable reference to an array of wrappers.
+      #inner_visibility fn from_array_mut<const #n: usize>(inner: &mut [#inner_type; #n]) -> &mut [Self; #n] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(inner as *mut [#inner_type; #n] as *mut [Self; #n]) }
+      }
+
+      /// Reinterpret a reference to an array of wrappers as a reference to an
+      /// array of the wrapped type.
+      pub fn as_inner_array<const #n: usize>(wrapped: &[Self; #n]) -> &[#inner_type; #n] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &*(wrapped as *const [Self; #n] as *const [#inner_type; #n]) }
+      }
+
+      /// Reinterpret a mutable reference to an array of wrappers as a mutable
+      /// reference to an array of the wrapped type.
+      #inner_visibility fn as_inner_array_mut<const #n: usize>(wrapped: &mut [Self; #n]) -> &mut [#inner_type; #n] {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { &mut *(wrapped as *mut [Self; #n] as *mut [#inner_type; #n]) }
+      }
+    }
+  }
+}
+
+fn impl_transparent_alloc(
+  details: &ast::StructDetails,
+  input: &ast::Struct,
+) -> proc_macro2::TokenStream {
+  let ast::StructDetails {
+    ident,
+    generics,
+    ..
+  } = details;
+  let ast::Struct {
+    inner_type,
+    inner_visibility,
+    ..
+  } = input;
+
+  let rust = rust_path(details);
+  let alloc = alloc_path(details);
+  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  let must_use = must_use(details);
+
+  quote! {
+    #[allow(dead_code)]
+    #[automatically_derived]
+    impl #impl_generics #ident #ty_generics #where_clause {
+      /// Turn a vector of the wrapped type into a vector of wrappers, reusing
+      /// the allocation.
+      #must_use
+      #inner_visibility fn wrap_vec(inner: #alloc::vec::Vec<#inner_type>) -> #alloc::vec::Vec<Self> {
+        let mut inner = #rust::mem::ManuallyDrop::new(inner);
+        let (ptr, len, cap) = (inner.as_mut_ptr(), inner.len(), inner.capacity());
+
+        // Sound because the struct is `#[repr(transparent)]` over the inner
+        // type, so the element layouts (and therefore allocation) match.
+        unsafe { #alloc::vec::Vec::from_raw_parts(ptr as *mut Self, len, cap) }
+      }
+
+      /// Turn a vector of wrappers into a vector of the wrapped type, reusing
+      /// the allocation.
+      #must_use
+      pub fn unwrap_vec(wrapped: #alloc::vec::Vec<Self>) -> #alloc::vec::Vec<#inner_type> {
+        let mut wrapped = #rust::mem::ManuallyDrop::new(wrapped);
+        let (ptr, len, cap) = (wrapped.as_mut_ptr(), wrapped.len(), wrapped.capacity());
+
+        // Sound because the struct is `#[repr(transparent)]` over the inner
+        // type, so the element layouts (and therefore allocation) match.
+        unsafe { #alloc::vec::Vec::from_raw_parts(ptr as *mut #inner_type, len, cap) }
+      }
+
+      /// Turn a boxed value of the wrapped type into a boxed wrapper, without
+      /// reallocating.
+      #must_use
+      #inner_visibility fn from_boxed(inner: #alloc::boxed::Box<#inner_type>) -> #alloc::boxed::Box<Self> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::boxed::Box::from_raw(#alloc::boxed::Box::into_raw(inner) as *mut Self) }
+      }
+
+      /// Turn a boxed wrapper into a boxed value of the wrapped type, without
+      /// reallocating.
+      #must_use
+      pub fn unwrap_boxed(wrapped: #alloc::boxed::Box<Self>) -> #alloc::boxed::Box<#inner_type> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::boxed::Box::from_raw(#alloc::boxed::Box::into_raw(wrapped) as *mut #inner_type) }
+      }
+
+      /// Turn a reference-counted value of the wrapped type into a
+      /// reference-counted wrapper, sharing the same allocation.
+      #must_use
+      #inner_visibility fn from_rc(inner: #alloc::rc::Rc<#inner_type>) -> #alloc::rc::Rc<Self> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::rc::Rc::from_raw(#alloc::rc::Rc::into_raw(inner) as *const Self) }
+      }
+
+      /// Turn a reference-counted wrapper into a reference-counted value of
+      /// the wrapped type, sharing the same allocation.
+      #must_use
+      pub fn unwrap_rc(wrapped: #alloc::rc::Rc<Self>) -> #alloc::rc::Rc<#inner_type> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::rc::Rc::from_raw(#alloc::rc::Rc::into_raw(wrapped) as *const #inner_type) }
+      }
+
+      /// Turn an atomically reference-counted value of the wrapped type into
+      /// an atomically reference-counted wrapper, sharing the same allocation.
+      #must_use
+      #inner_visibility fn from_arc(inner: #alloc::sync::Arc<#inner_type>) -> #alloc::sync::Arc<Self> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::sync::Arc::from_raw(#alloc::sync::Arc::into_raw(inner) as *const Self) }
+      }
+
+      /// Turn an atomically reference-counted wrapper into an atomically
+      /// reference-counted value of the wrapped type, sharing the same
+      /// allocation.
+      #must_use
+      pub fn unwrap_arc(wrapped: #alloc::sync::Arc<Self>) -> #alloc::sync::Arc<#inner_type> {
+        // Sound because the struct is `#[repr(transparent)]` over the inner type.
+        unsafe { #alloc::sync::Arc::from_raw(#alloc::sync::Arc::into_raw(wrapped) as *const #inner_type) }
+      }
+
+      /// Give up ownership of a boxed wrapper, getting back a raw pointer to
+      /// the wrapped value; use `from_raw()` to take ownership back again.
+      #must_use
+      pub fn into_raw(wrapped: #alloc::boxed::Box<Self>) -> *mut #inner_type {
+        #alloc::boxed::Box::into_raw(wrapped) as *mut #inner_type
+      }
+
+      /// Take ownership of a wrapped value through a raw pointer.
+      ///
+      /// # Safety
+      ///
+      /// This has the same requirements as `Box::from_raw()`; usually `ptr`
+      /// should have come from `into_raw()`.
+      #inner_visibility unsafe fn from_raw(ptr: *mut #inner_type) -> #alloc::boxed::Box<Self> {
+        unsafe { #alloc::boxed::Box::from_raw(ptr as *mut Self) }
+      }
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+const RUST: &str = "std";
+#[cfg(not(feature = "std"))]
+const RUST: &str = "core";
+
+/// Where generated code finds `core` items like `Deref`: `::std` or `::core`
+/// depending on our features, or wherever `#[shrinkwrap(std_path = "..")]`
+/// says, for crates that go through a facade.
+fn rust_path(details: &ast::StructDetails) -> TokenStream {
+  match details.std_path {
+    Some(ref path) => quote!( #path ),
+    None => {
+      let rust = syn::Ident::new(RUST, Span::call_site());
+
+      quote!( ::#rust )
+    }
+  }
+}
+
+/// Like `rust_path()`, for items that need an allocator: `::std` with the
+/// `std` feature, and otherwise `::alloc`, which the deriving crate has to
+/// bring in with `extern crate alloc;`.
+fn alloc_path(details: &ast::StructDetails) -> TokenStream {
+  match details.std_path {
+    Some(ref path) => quote!( #path ),
+    None if cfg!(feature = "std") => quote!( ::std ),
+    None => quote!( ::alloc ),
+  }
+}
+
+/// Like `rust_path()`, for items that only `std` has.
+fn std_path(details: &ast::StructDetails) -> TokenStream {
+  match details.std_path {
+    Some(ref path) => quote!( #path ),
+    None => quote!( ::std ),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use syn;
+
+  use super::*;
+
+  #[test]
+  fn test_type_name() {
+    let name = |ty: &str| type_name(&syn::parse_str::<syn::Type>(ty).unwrap());
+
+    assert_eq!(name("String"), "String");
+    assert_eq!(name("Vec<u8>"), "Vec<u8>");
+    assert_eq!(name("::std::collections::HashMap<K, V>"), "::std::collections::HashMap<K, V>");
+    assert_eq!(name("&'a [u8; 4]"), "&'a [u8; 4]");
+    assert_eq!(name("(u32, Box<str>)"), "(u32, Box<str>)");
+  }
+
+  #[test]
+  fn test_option_inner_visibility() {
+    let expand = |input: &str| {
+      let (details, input) = ast::validate_derive_input(syn::parse_str(input).unwrap()).unwrap();
+
+      impl_option_inner(&details, &input).to_string()
+    };
+
+    let private = expand("#[shrinkwrap(option)] struct Maybe(Option<u32>);");
+    let crate_wide = expand("#[shrinkwrap(option)] pub(crate) struct Maybe(Option<u32>);");
+
+    assert!(!private.contains("pub"));
+    assert!(crate_wide.contains("pub (crate) fn is_some"));
+  }
+
+  #[test]
+  fn test_derive_mut_checks_visibility() {
+    let expand = |input: &str| expand_shrinkwrap_mut(syn::parse_str(input).unwrap());
+
+    let error = expand("pub struct Secret(String);").err().unwrap();
+    assert!(error.to_string().contains("inner field is less visible"));
+
+    assert!(expand("pub struct Public(pub String);").is_ok());
+    assert!(expand("pub(crate) struct Internal(pub(crate) String);").is_ok());
+    assert!(expand(
+      "#[shrinkwrap(unsafe_ignore_visibility)]
+      pub struct Secret(String);"
+    )
+    .is_ok());
+  }
+
+  #[test]
+  fn test_visibility_policy() {
+    let expand = |input: &str| expand_shrinkwrap_mut(syn::parse_str(input).unwrap());
+
+    let error = expand(
+      r#"#[shrinkwrap(visibility_policy = "error")]
+      pub(in a::b) struct Unclear(pub(super) u32);"#,
+    )
+    .err()
+    .unwrap();
+    assert!(error.to_string().contains("can't figure out whether"));
+
+    // The default only falls back to an error when it can't be a warning.
+    let default = expand("pub(in a::b) struct Unclear(pub(super) u32);");
+    assert_eq!(default.is_ok(), diagnostics::CAN_WARN);
+
+    assert!(expand(
+      r#"#[shrinkwrap(visibility_policy = "allow")]
+      pub(in a::b) struct Unclear(pub(super) u32);"#
+    )
+    .is_ok());
+    assert!(expand(
+      r#"#[shrinkwrap(visibility_policy = "allow")]
+      pub struct Secret(String);"#
+    )
+    .is_err());
+
+    let error = expand(
+      r#"#[shrinkwrap(visibility_policy = "ignore")]
+      pub struct Public(pub String);"#,
+    )
+    .err()
+    .unwrap();
+    assert!(error.to_string().contains("should be \"error\""));
+  }
+}
\ No newline at end of file
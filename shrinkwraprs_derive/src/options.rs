@@ -0,0 +1,736 @@
+//! Both derives are configured through the same struct-level
+//! `#[shrinkwrap(..)]` attributes, so we parse all of them in a single pass
+//! here. Every option has to be one we know about; a typo like
+//! `#[shrinkwrap(mutible)]` is an error rather than being silently ignored.
+//! Errors point at the option they're about.
+
+use syn;
+use syn::ext::IdentExt;
+use syn::{Lit, Meta, MetaList, MetaNameValue, NestedMeta};
+
+use ast::{
+  tagged_attrs, CfgGate, Delegates, Encoding, ExtTrait, Inline, LockBackend, MainField,
+  MethodNames, ShrinkwrapFlags, Traits, VisibilityPolicy,
+};
+use diagnostics;
+
+/// Everything that can be configured with struct-level attributes.
+pub struct Options {
+  pub flags: ShrinkwrapFlags,
+  pub traits: Traits,
+  pub names: MethodNames,
+  pub ext_trait: Option<ExtTrait>,
+  pub delegates: Delegates,
+  /// If we should generate a `new()` constructor, what visibility it has.
+  pub constructor: Option<syn::Visibility>,
+  /// If we should generate `inner_mut()` instead of the mutable traits, what
+  /// visibility it has.
+  pub mut_access: Option<syn::Visibility>,
+  pub main_field: Option<MainField>,
+  /// With a nested main field like `"config.addr"`, everything after the
+  /// first step, along with the type it ends up at.
+  pub main_path: Vec<syn::Member>,
+  pub main_type: Option<syn::Type>,
+  /// What `Deref` should point at, if not the inner type itself.
+  pub deref_target: Option<syn::Type>,
+  /// For the borrowed half of a `Path`/`PathBuf`-style pair, the owned half.
+  pub owned: Option<syn::Type>,
+  /// For the owned half of such a pair, the borrowed half.
+  pub borrowed: Option<syn::Type>,
+  pub cfgs: Vec<CfgGate>,
+  /// Where generated code should find the standard library, if not `::std`
+  /// or `::core`.
+  pub std_path: Option<syn::Path>,
+  pub inline: Inline,
+  pub visibility_policy: VisibilityPolicy,
+  /// The value `#[shrinkwrap(gen_tests = "..")]` starts from, if not the
+  /// inner type's `Default`.
+  pub test_sample: Option<syn::Expr>,
+  pub display_encoding: Option<Encoding>,
+  pub lock_backend: LockBackend,
+}
+
+pub fn parse_options(ident: &syn::Ident, attrs: &[syn::Attribute]) -> syn::Result<Options> {
+  let mut options = Options {
+    flags: ShrinkwrapFlags::empty(),
+    traits: Traits::all(),
+    names: MethodNames::new(),
+    ext_trait: None,
+    delegates: Delegates {
+      constructors: vec![],
+      owned: vec![],
+    },
+    constructor: None,
+    mut_access: None,
+    main_field: None,
+    main_path: vec![],
+    main_type: None,
+    deref_target: None,
+    owned: None,
+    borrowed: None,
+    cfgs: vec![],
+    std_path: None,
+    inline: Inline::Hint,
+    visibility_policy: if diagnostics::CAN_WARN {
+      VisibilityPolicy::Warn
+    } else {
+      VisibilityPolicy::Error
+    },
+    test_sample: None,
+    display_encoding: None,
+    lock_backend: LockBackend::Std,
+  };
+  let mut listed_traits = None;
+  let mut opted_out = Traits::empty();
+
+  for option in tagged_attrs("shrinkwrap", attrs)? {
+    match option {
+      NestedMeta::Meta(Meta::Path(path)) => {
+        let name = option_name(&path)?;
+
+        match name.as_str() {
+          "mutable" => options.flags |= ShrinkwrapFlags::SW_MUT,
+          "unsafe_ignore_visibility" => options.flags |= ShrinkwrapFlags::SW_IGNORE_VIS,
+          "unsafe_ignore_borrow_contract" => options.flags |= ShrinkwrapFlags::SW_IGNORE_BORROW,
+          "transparent" => options.flags |= ShrinkwrapFlags::SW_TRANSPARENT,
+          "cow" => options.flags |= ShrinkwrapFlags::SW_COW,
+          "constructor_into" => options.flags |= ShrinkwrapFlags::SW_CTOR_INTO,
+          "const_fns" => options.flags |= ShrinkwrapFlags::SW_CONST_FNS,
+          "copy_get" => options.flags |= ShrinkwrapFlags::SW_COPY_GET,
+          "with_fields" => options.flags |= ShrinkwrapFlags::SW_WITH_FIELDS,
+          "must_use" => options.flags |= ShrinkwrapFlags::SW_MUST_USE,
+          "as_ref_all" => options.flags |= ShrinkwrapFlags::SW_AS_REF_ALL,
+          "deref_via_inner" => options.flags |= ShrinkwrapFlags::SW_DEREF_VIA,
+          "as_ref_via_inner" => options.flags |= ShrinkwrapFlags::SW_AS_REF_VIA,
+          "option" => options.flags |= ShrinkwrapFlags::SW_OPTION,
+          "slice" => options.flags |= ShrinkwrapFlags::SW_SLICE,
+          "lock" => options.flags |= ShrinkwrapFlags::SW_LOCK,
+          "cell" => options.flags |= ShrinkwrapFlags::SW_CELL,
+          "atomic" => options.flags |= ShrinkwrapFlags::SW_ATOMIC,
+          "uuid" => options.flags |= ShrinkwrapFlags::SW_UUID,
+          "mem_helpers" => options.flags |= ShrinkwrapFlags::SW_MEM_HELPERS,
+          "accessors" => options.flags |= ShrinkwrapFlags::SW_ACCESSORS,
+          "option_result_helpers" => options.flags |= ShrinkwrapFlags::SW_OPT_RESULT,
+          "iter_helpers" => options.flags |= ShrinkwrapFlags::SW_ITER,
+          // Only ever written by `newtype!`, for structs with a check that
+          // every value has to go through.
+          "__checked" => options.flags |= ShrinkwrapFlags::SW_CHECKED,
+          "transitive" => options.flags |= ShrinkwrapFlags::SW_TRANSITIVE,
+          "path_like" => options.flags |= ShrinkwrapFlags::SW_PATH_LIKE,
+          "bytes" => options.flags |= ShrinkwrapFlags::SW_BYTES,
+          "forward_as_ref" => options.flags |= ShrinkwrapFlags::SW_FWD_AS_REF,
+          "forward_borrow" => options.flags |= ShrinkwrapFlags::SW_FWD_BORROW,
+          "equivalent" => options.flags |= ShrinkwrapFlags::SW_EQUIVALENT,
+          "assert_zero_cost" => options.flags |= ShrinkwrapFlags::SW_ZERO_COST,
+          "assert_send_sync" => options.flags |= ShrinkwrapFlags::SW_SEND_SYNC,
+          "gen_tests" => options.flags |= ShrinkwrapFlags::SW_GEN_TESTS,
+          "unsized_inner" => options.flags |= ShrinkwrapFlags::SW_UNSIZED,
+          "no_deref" => opted_out |= Traits::DEREF | Traits::DEREF_MUT,
+          "no_borrow" => opted_out |= Traits::BORROW | Traits::BORROW_MUT,
+          "no_as_ref" => opted_out |= Traits::AS_REF,
+          "no_deref_mut" => opted_out |= Traits::DEREF_MUT,
+          "no_borrow_mut" => opted_out |= Traits::BORROW_MUT,
+          "no_as_mut" => opted_out |= Traits::AS_MUT,
+          "no_inline" => options.inline = Inline::Omit,
+          "constructor" => options.constructor = Some(syn::parse_quote!(pub)),
+          "ext_trait" => options.ext_trait = Some(ext_trait(ident, vec![])?),
+          _ => return Err(unknown_option(&path)),
+        }
+      }
+      NestedMeta::Meta(Meta::List(MetaList { path, nested, .. })) => {
+        let name = option_name(&path)?;
+        let nested = nested.into_iter().collect::<Vec<_>>();
+
+        match name.as_str() {
+          "traits" => *listed_traits.get_or_insert(Traits::empty()) |= traits(nested)?,
+          "rename" => rename(&mut options.names, nested)?,
+          "ext_trait" => options.ext_trait = Some(ext_trait(ident, nested)?),
+          "delegate_constructors" => {
+            for sig in nested {
+              options.delegates.constructors.push(delegate_signature(sig)?);
+            }
+          }
+          "cfg" => options.cfgs.push(cfg_gate(&path, nested)?),
+          "inline" => options.inline = inline(&path, nested)?,
+          "delegate_owned" => {
+            for sig in nested {
+              options.delegates.owned.push(delegate_signature(sig)?);
+            }
+          }
+          _ => return Err(unknown_option(&path)),
+        }
+      }
+      NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => {
+        let name = option_name(&path)?;
+
+        match name.as_str() {
+          "constructor_vis" => {
+            options.constructor = Some(visibility_option("constructor_vis", lit)?)
+          }
+          "mut_access" => options.mut_access = Some(visibility_option("mut_access", lit)?),
+          "main_field" => {
+            let (field, path) = main_field(lit)?;
+
+            options.main_field = Some(field);
+            options.main_path = path;
+          }
+          "main_type" => options.main_type = Some(type_option("main_type", "String", lit)?),
+          "deref_target" => {
+            options.deref_target = Some(type_option("deref_target", "str", lit)?)
+          }
+          "owned" => options.owned = Some(type_option("owned", "EmailBuf", lit)?),
+          "borrowed" => options.borrowed = Some(type_option("borrowed", "Email", lit)?),
+          "std_path" => options.std_path = Some(std_path(lit)?),
+          "visibility_policy" => options.visibility_policy = visibility_policy(lit)?,
+          "gen_tests" => {
+            options.flags |= ShrinkwrapFlags::SW_GEN_TESTS;
+            options.test_sample = Some(test_sample(lit)?);
+          }
+          "display_encoding" => options.display_encoding = Some(display_encoding(lit)?),
+          "main_field_mut" => {
+            no_mut_field(lit)?;
+            options.flags |= ShrinkwrapFlags::SW_NO_MUT;
+          }
+          "lock" => {
+            options.flags |= ShrinkwrapFlags::SW_LOCK;
+            options.lock_backend = lock_backend(lit)?;
+          }
+          _ => return Err(unknown_option(&path)),
+        }
+      }
+      NestedMeta::Lit(lit) => {
+        return Err(syn::Error::new_spanned(
+          lit,
+          "shrinkwraprs: #[shrinkwrap(..)] expects options like `mutable`,
+not bare literals",
+        ))
+      }
+    }
+  }
+
+  if options.flags.contains(ShrinkwrapFlags::SW_CTOR_INTO) && options.constructor.is_none() {
+    options.constructor = Some(syn::parse_quote!(pub));
+  }
+
+  options.traits = listed_traits.unwrap_or(options.traits) - opted_out;
+  validate_traits(ident, options.traits)?;
+
+  Ok(options)
+}
+
+/// Everything that can be configured with field-level attributes.
+#[derive(Default)]
+pub struct FieldOptions {
+  pub main_field: bool,
+  pub main_field_mut: bool,
+  pub ignore: bool,
+  /// Whether the wrapper should also borrow as this field.
+  pub borrow: bool,
+  /// What to call the field in method names like `with_*()`, if not its own
+  /// name.
+  pub rename: Option<syn::Ident>,
+}
+
+/// Field-level options can be flags like `#[shrinkwrap(borrow)]` or spelled
+/// out like `#[shrinkwrap(borrow = true)]`, and be combined freely, e.g.
+/// `#[shrinkwrap(main_field, borrow)]`.
+pub fn parse_field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+  let mut options = FieldOptions::default();
+
+  for option in tagged_attrs("shrinkwrap", &field.attrs)? {
+    let (path, value) = match &option {
+      NestedMeta::Meta(Meta::Path(path)) => (path, None),
+      NestedMeta::Meta(Meta::NameValue(MetaNameValue { path, lit, .. })) => (path, Some(lit)),
+      _ => return Err(unknown_field_option(&option)),
+    };
+    let name = match path.get_ident() {
+      Some(ident) => ident.to_string(),
+      None => return Err(unknown_field_option(&option)),
+    };
+
+    match (name.as_str(), value) {
+      ("rename", Some(Lit::Str(name))) => {
+        let renamed = name.parse().map_err(|_| {
+          syn::Error::new_spanned(
+            name,
+            "shrinkwraprs: a field's rename should be an identifier in a string,
+like #[shrinkwrap(rename = \"addr\")]",
+          )
+        })?;
+
+        options.rename = Some(renamed);
+      }
+      (flag, value) => {
+        let on = match value {
+          None => true,
+          Some(Lit::Bool(on)) => on.value,
+          Some(..) => return Err(unknown_field_option(&option)),
+        };
+
+        match flag {
+          "main_field" => options.main_field = on,
+          "main_field_mut" => options.main_field_mut = on,
+          "ignore" => options.ignore = on,
+          "borrow" => options.borrow = on,
+          _ => return Err(unknown_field_option(&option)),
+        }
+      }
+    }
+  }
+
+  Ok(options)
+}
+
+// As with struct-level options, anything we don't recognize is more likely a
+// typo than something we should ignore.
+fn unknown_field_option(option: &NestedMeta) -> syn::Error {
+  syn::Error::new_spanned(
+    option,
+    "shrinkwraprs: the only options fields can have are
+#[shrinkwrap(main_field)], #[shrinkwrap(main_field_mut)],
+#[shrinkwrap(ignore)], #[shrinkwrap(borrow)] and
+#[shrinkwrap(rename = \"..\")]",
+  )
+}
+
+fn option_name(path: &syn::Path) -> syn::Result<String> {
+  match path.get_ident() {
+    Some(ident) => Ok(ident.to_string()),
+    None => Err(unknown_option(path)),
+  }
+}
+
+fn unknown_option(path: &syn::Path) -> syn::Error {
+  syn::Error::new_spanned(
+    path,
+    format!(
+      "shrinkwraprs: #[shrinkwrap({})] isn't an option that shrinkwraprs
+knows about",
+      quote!(#path).to_string().replace(' ', "")
+    ),
+  )
+}
+
+/// `cfg(feature = "std", traits(Borrow, AsRef))` takes a `cfg` predicate,
+/// along with the traits to gate on it.
+fn cfg_gate(path: &syn::Path, nested: Vec<NestedMeta>) -> syn::Result<CfgGate> {
+  let (listed, predicates): (Vec<_>, Vec<_>) = nested.into_iter().partition(|meta| match meta {
+    NestedMeta::Meta(Meta::List(MetaList { path, .. })) => path.is_ident("traits"),
+    _ => false,
+  });
+  let mut traits = Traits::empty();
+
+  for listed in listed {
+    if let NestedMeta::Meta(Meta::List(MetaList { nested, .. })) = listed {
+      traits |= self::traits(nested.into_iter().collect())?;
+    }
+  }
+
+  let mut predicates = predicates.into_iter();
+
+  match (predicates.next(), predicates.next()) {
+    (Some(predicate), None) if !traits.is_empty() => Ok(CfgGate { traits, predicate }),
+    _ => Err(syn::Error::new_spanned(
+      path,
+      "shrinkwraprs: cfg(..) takes a single cfg predicate and the traits
+to gate on it, like
+#[shrinkwrap(cfg(feature = \"std\", traits(Borrow, AsRef)))]",
+    )),
+  }
+}
+
+/// `#[shrinkwrap(traits(Deref, AsRef))]` restricts which traits we
+/// implement to exactly the ones listed.
+fn traits(names: Vec<NestedMeta>) -> syn::Result<Traits> {
+  let mut traits = Traits::empty();
+
+  for meta in names {
+    let name = match &meta {
+      NestedMeta::Meta(Meta::Path(path)) => path.get_ident().map(|ident| ident.to_string()),
+      _ => None,
+    };
+
+    traits |= match name.as_deref() {
+      Some("Deref") => Traits::DEREF,
+      Some("Borrow") => Traits::BORROW,
+      Some("AsRef") => Traits::AS_REF,
+      Some("DerefMut") => Traits::DEREF_MUT,
+      Some("BorrowMut") => Traits::BORROW_MUT,
+      Some("AsMut") => Traits::AS_MUT,
+      _ => {
+        return Err(syn::Error::new_spanned(
+          meta,
+          "shrinkwraprs: traits(..) only knows about Deref, Borrow, AsRef,
+DerefMut, BorrowMut and AsMut",
+        ))
+      }
+    };
+  }
+
+  Ok(traits)
+}
+
+/// `DerefMut` and `BorrowMut` only make sense alongside `Deref` and
+/// `Borrow`, since they're subtraits.
+fn validate_traits(ident: &syn::Ident, traits: Traits) -> syn::Result<()> {
+  if traits.contains(Traits::DEREF_MUT) && !traits.contains(Traits::DEREF) {
+    return Err(syn::Error::new_spanned(
+      ident,
+      "shrinkwraprs: traits(..) can't implement DerefMut without Deref,
+since DerefMut is a subtrait of Deref.",
+    ));
+  }
+  if traits.contains(Traits::BORROW_MUT) && !traits.contains(Traits::BORROW) {
+    return Err(syn::Error::new_spanned(
+      ident,
+      "shrinkwraprs: traits(..) can't implement BorrowMut without Borrow,
+since BorrowMut is a subtrait of Borrow.",
+    ));
+  }
+
+  Ok(())
+}
+
+/// Options like `#[shrinkwrap(constructor_vis = "...")]`, which asks for a
+/// `new()` with the given visibility.
+fn visibility_option(option: &str, lit: Lit) -> syn::Result<syn::Visibility> {
+  let vis = match &lit {
+    Lit::Str(vis) => vis.parse().ok(),
+    _ => None,
+  };
+
+  vis.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      format!(
+        "shrinkwraprs: {} should be a visibility in a string,
+like #[shrinkwrap({} = \"pub(crate)\")]",
+        option, option
+      ),
+    )
+  })
+}
+
+/// `#[shrinkwrap(main_field = "addr")]` picks the main field by name, and
+/// `#[shrinkwrap(main_field = 2)]` by its position. Names can also be paths
+/// like `"config.addr"` or `"0.addr"` that reach into one of the fields.
+fn main_field(lit: Lit) -> syn::Result<(MainField, Vec<syn::Member>)> {
+  match lit {
+    Lit::Str(path) => {
+      let mut members = vec![];
+
+      for member in path.value().split('.') {
+        let member = syn::parse_str::<syn::Member>(member.trim()).map_err(|_| {
+          syn::Error::new_spanned(
+            &path,
+            format!(
+              "shrinkwraprs: `{}` in main_field isn't a field name or index",
+              member
+            ),
+          )
+        })?;
+
+        members.push(member);
+      }
+
+      // Splitting always gives us at least one member.
+      let field = match members.remove(0) {
+        syn::Member::Named(ident) => MainField::Named(ident.to_string()),
+        syn::Member::Unnamed(index) => MainField::Index(index.index as usize),
+      };
+
+      Ok((field, members))
+    }
+    Lit::Int(index) => {
+      let field = index.base10_parse().map_err(|_| {
+        syn::Error::new_spanned(&index, "shrinkwraprs: main_field indices have to fit in a usize")
+      })?;
+
+      Ok((MainField::Index(field), vec![]))
+    }
+    _ => Err(syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: main_field on a struct should be a field name or index,
+like #[shrinkwrap(main_field = \"addr\")] or #[shrinkwrap(main_field = 2)]",
+    )),
+  }
+}
+
+/// Options like `main_type` and `deref_target` take a type, which has to be
+/// in a string since attributes can only hold literals.
+fn type_option(option: &str, example: &str, lit: Lit) -> syn::Result<syn::Type> {
+  let ty = match &lit {
+    Lit::Str(ty) => ty.parse().ok(),
+    _ => None,
+  };
+
+  ty.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      format!(
+        "shrinkwraprs: {} should be a type in a string,
+like #[shrinkwrap({} = \"{}\")]",
+        option, option, example
+      ),
+    )
+  })
+}
+
+/// On the struct, `main_field_mut` can only say there's no mutable field at
+/// all; a field is picked by marking it instead.
+fn no_mut_field(lit: Lit) -> syn::Result<()> {
+  match &lit {
+    Lit::Str(none) if none.value() == "none" => Ok(()),
+    _ => Err(syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: on the struct, main_field_mut can only be \"none\",
+like #[shrinkwrap(main_field_mut = \"none\")]. To pick the field
+that writes go to, mark it with #[shrinkwrap(main_field_mut)].",
+    )),
+  }
+}
+
+fn test_sample(lit: Lit) -> syn::Result<syn::Expr> {
+  let sample = match &lit {
+    Lit::Str(sample) => sample.parse().ok(),
+    _ => None,
+  };
+
+  sample.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: gen_tests should be given a value of the inner
+type in a string, like
+#[shrinkwrap(gen_tests = \"String::from(\\\"hi\\\")\")]",
+    )
+  })
+}
+
+fn display_encoding(lit: Lit) -> syn::Result<Encoding> {
+  let encoding = match &lit {
+    Lit::Str(encoding) => match encoding.value().as_str() {
+      "hex" => Some(Encoding::Hex),
+      "base58" => Some(Encoding::Base58),
+      "base64" => Some(Encoding::Base64),
+      _ => None,
+    },
+    _ => None,
+  };
+
+  encoding.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: display_encoding should be \"hex\", \"base58\" or
+\"base64\", like #[shrinkwrap(display_encoding = \"hex\")]",
+    )
+  })
+}
+
+fn lock_backend(lit: Lit) -> syn::Result<LockBackend> {
+  match &lit {
+    Lit::Str(backend) if backend.value() == "std" => Ok(LockBackend::Std),
+    Lit::Str(backend) if backend.value() == "parking_lot" => Ok(LockBackend::ParkingLot),
+    _ => Err(syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: lock should be \"std\" or \"parking_lot\", like
+#[shrinkwrap(lock = \"parking_lot\")]",
+    )),
+  }
+}
+
+fn std_path(lit: Lit) -> syn::Result<syn::Path> {
+  let path = match &lit {
+    Lit::Str(path) => path.parse().ok(),
+    _ => None,
+  };
+
+  path.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: std_path should be a path in a string,
+like #[shrinkwrap(std_path = \"::my_facade\")]",
+    )
+  })
+}
+
+fn visibility_policy(lit: Lit) -> syn::Result<VisibilityPolicy> {
+  let policy = match &lit {
+    Lit::Str(policy) => match policy.value().as_str() {
+      "error" => Some(VisibilityPolicy::Error),
+      "warn" => Some(VisibilityPolicy::Warn),
+      "allow" => Some(VisibilityPolicy::Allow),
+      _ => None,
+    },
+    _ => None,
+  };
+
+  policy.ok_or_else(|| {
+    syn::Error::new_spanned(
+      lit,
+      "shrinkwraprs: visibility_policy should be \"error\", \"warn\" or
+\"allow\", like #[shrinkwrap(visibility_policy = \"warn\")]",
+    )
+  })
+}
+
+/// `#[shrinkwrap(inline(always))]`; plain `#[inline]` is the default, and
+/// `#[shrinkwrap(no_inline)]` turns it off.
+fn inline(path: &syn::Path, nested: Vec<NestedMeta>) -> syn::Result<Inline> {
+  let message = "shrinkwraprs: the only inline option is #[shrinkwrap(inline(always))];
+#[inline] is already the default, and #[shrinkwrap(no_inline)] leaves it out";
+
+  match nested.as_slice() {
+    [NestedMeta::Meta(Meta::Path(always))] if always.is_ident("always") => Ok(Inline::Always),
+    [] => Err(syn::Error::new_spanned(path, message)),
+    [other, ..] => Err(syn::Error::new_spanned(other, message)),
+  }
+}
+
+fn rename(names: &mut MethodNames, renames: Vec<NestedMeta>) -> syn::Result<()> {
+  for rename in renames {
+    let (method, name) = match rename {
+      NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+        path,
+        lit: Lit::Str(name),
+        ..
+      })) => (path, name),
+      other => {
+        return Err(syn::Error::new_spanned(
+          other,
+          "shrinkwraprs: renames should look like
+#[shrinkwrap(rename(into_inner = \"into_raw\"))]",
+        ))
+      }
+    };
+    let renamed: syn::Ident = name.parse().map_err(|_| {
+      syn::Error::new_spanned(
+        &name,
+        "shrinkwraprs: method names given to rename(..) must be identifiers",
+      )
+    })?;
+
+    match names.by_name(&method) {
+      Some(slot) => *slot = renamed,
+      None => {
+        return Err(syn::Error::new_spanned(
+          method,
+          "shrinkwraprs: rename(..) doesn't know about that method",
+        ))
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// `#[shrinkwrap(ext_trait)]` asks for a trait named after the struct, so
+/// `Email` gets `IntoEmail::into_email()`; either name can be overridden
+/// with `#[shrinkwrap(ext_trait(name = "ToEmail", method = "to_email"))]`.
+fn ext_trait(ident: &syn::Ident, options: Vec<NestedMeta>) -> syn::Result<ExtTrait> {
+  // `format_ident!()` drops the `r#` from raw identifiers for us, but
+  // `snake_case()` needs it gone up front.
+  let mut names = ExtTrait {
+    name: quote::format_ident!("Into{}", ident),
+    method: quote::format_ident!("into_{}", snake_case(&ident.unraw().to_string())),
+  };
+
+  for option in options {
+    let (key, name) = match option {
+      NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+        path,
+        lit: Lit::Str(name),
+        ..
+      })) => (path, name),
+      other => {
+        return Err(syn::Error::new_spanned(
+          other,
+          "shrinkwraprs: ext_trait options should look like
+#[shrinkwrap(ext_trait(name = \"ToEmail\", method = \"to_email\"))]",
+        ))
+      }
+    };
+    let renamed: syn::Ident = name.parse().map_err(|_| {
+      syn::Error::new_spanned(
+        &name,
+        "shrinkwraprs: names given to ext_trait(..) must be identifiers",
+      )
+    })?;
+
+    if key.is_ident("name") {
+      names.name = renamed;
+    } else if key.is_ident("method") {
+      names.method = renamed;
+    } else {
+      return Err(syn::Error::new_spanned(
+        key,
+        "shrinkwraprs: ext_trait(..) only knows about `name` and `method`",
+      ));
+    }
+  }
+
+  Ok(names)
+}
+
+/// `#[shrinkwrap(delegate_constructors("new", "with_capacity(capacity: usize)"))]`
+/// lists signatures, minus the `fn`; a bare name means no arguments. The
+/// same goes for `#[shrinkwrap(delegate_owned("into_bytes(self) -> Vec<u8>"))]`.
+fn delegate_signature(meta: NestedMeta) -> syn::Result<syn::Signature> {
+  let lit = match meta {
+    NestedMeta::Lit(Lit::Str(lit)) => lit,
+    other => {
+      return Err(syn::Error::new_spanned(
+        other,
+        "shrinkwraprs: delegated functions should be given as strings, like
+#[shrinkwrap(delegate_constructors(\"with_capacity(capacity: usize)\"))]",
+      ))
+    }
+  };
+  let sig = lit.value();
+  let sig = if sig.contains('(') {
+    format!("fn {}", sig)
+  } else {
+    format!("fn {}()", sig)
+  };
+
+  // Parsing through a literal with the original's span means that later
+  // errors about the signature point at the string it came from.
+  syn::LitStr::new(&sig, lit.span()).parse().map_err(|_| {
+    syn::Error::new_spanned(
+      &lit,
+      format!("shrinkwraprs: couldn't parse `{}` as a function signature", sig),
+    )
+  })
+}
+
+/// `CodeSpan` becomes `code_span`, and `HTTPRequest` becomes `http_request`.
+fn snake_case(name: &str) -> String {
+  let chars = name.chars().collect::<Vec<_>>();
+  let mut snake = String::new();
+
+  for (i, &c) in chars.iter().enumerate() {
+    if c.is_uppercase() && i > 0 {
+      let prev = chars[i - 1];
+      let next_is_lower = chars.get(i + 1).map(|next| next.is_lowercase()) == Some(true);
+
+      if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+        snake.push('_');
+      }
+    }
+    snake.extend(c.to_lowercase());
+  }
+
+  snake
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_snake_case() {
+    assert_eq!(snake_case("Email"), "email");
+    assert_eq!(snake_case("CodeSpan"), "code_span");
+    assert_eq!(snake_case("HTTPRequest"), "http_request");
+    assert_eq!(snake_case("Utf8Str"), "utf8_str");
+  }
+}
@@ -7,8 +7,6 @@
 
 use syn;
 
-use itertools::Itertools;
-
 // When checking for visibility containment, we can make use of the guarantee
 // that the langauge provides us that any visibility path must be a parent
 // module of the current one. This means, for instance, that we don't have
@@ -62,27 +60,47 @@ pub fn field_visibility(
   }
 
   // If the field is marked `pub`, then we know it's definitely visible...
-  if &field_vis == &vec![PathComponent::Pub] {
+  if field_vis == vec![PathComponent::Pub] {
     return FieldVisibility::Visible;
   }
 
   // ...and if that's not the case, but the struct is marked `pub`, we know
   // the field is definitely restricted.
-  if &struct_vis == &vec![PathComponent::Pub] {
+  if struct_vis == vec![PathComponent::Pub] {
     return FieldVisibility::Restricted;
   }
 
   check_head(&struct_vis, &field_vis)
 }
 
+/// The visibility that's no more visible than the struct or any of the given
+/// fields, for methods that hand all of them out at once. If we can't tell
+/// how two of them relate, we play it safe and go private.
+pub fn least_visible<'a, I>(struct_vis: &syn::Visibility, field_vises: I) -> syn::Visibility
+where
+  I: IntoIterator<Item = &'a syn::Visibility>,
+{
+  let mut least = struct_vis.clone();
+
+  for field_vis in field_vises {
+    match field_visibility(&least, field_vis) {
+      FieldVisibility::Visible => (),
+      FieldVisibility::Restricted => least = field_vis.clone(),
+      FieldVisibility::CantDetermine => return syn::Visibility::Inherited,
+    }
+  }
+
+  least
+}
+
 fn to_path(path: &syn::Visibility) -> Vec<PathComponent> {
   use syn::Visibility::*;
 
   match path {
-    &Public(..) => vec![PathComponent::Pub],
-    &Crate(..) => vec![PathComponent::Pub, PathComponent::Crate],
-    &Inherited => vec![PathComponent::Inherited],
-    &Restricted(ref vis) => to_path_restricted(&vis.path),
+    Public(..) => vec![PathComponent::Pub],
+    Crate(..) => vec![PathComponent::Pub, PathComponent::Crate],
+    Inherited => vec![PathComponent::Inherited],
+    Restricted(vis) => to_path_restricted(&vis.path),
   }
 }
 
@@ -91,7 +109,7 @@ fn to_path_restricted(path: &syn::Path) -> Vec<PathComponent> {
     .segments
     .iter()
     .map(|path_segment| &path_segment.ident)
-    .collect_vec();
+    .collect::<Vec<_>>();
 
   match segments.split_first() {
     None => vec![],
@@ -0,0 +1,277 @@
+//! The text encodings behind `#[shrinkwrap(display_encoding = "..")]`. They
+//! live here rather than in the generated code so that every ID newtype in a
+//! crate shares one copy, and so that the derive doesn't need to know how
+//! base58 works.
+
+use core::fmt;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use alloc::vec::Vec;
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+/// Bitcoin's alphabet, which leaves out `0`, `O`, `I` and `l`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+  /// Lowercase hex, two digits per byte.
+  Hex,
+  /// Bitcoin-style base58. Needs an allocator, since every digit depends on
+  /// every byte.
+  #[cfg(any(feature = "std", feature = "alloc"))]
+  Base58,
+  /// Standard, padded base64.
+  Base64,
+}
+
+/// Why a string couldn't be parsed into a `display_encoding` newtype.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+  kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+  InvalidCharacter(char),
+  InvalidLength,
+}
+
+impl DecodeError {
+  fn invalid_character(c: char) -> DecodeError {
+    DecodeError {
+      kind: ErrorKind::InvalidCharacter(c),
+    }
+  }
+
+  fn invalid_length() -> DecodeError {
+    DecodeError {
+      kind: ErrorKind::InvalidLength,
+    }
+  }
+}
+
+impl fmt::Display for DecodeError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.kind {
+      ErrorKind::InvalidCharacter(c) => write!(f, "invalid character {:?} in encoded bytes", c),
+      ErrorKind::InvalidLength => f.write_str("encoded bytes have the wrong length"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for DecodeError {}
+
+/// Somewhere to decode bytes into.
+pub trait Output {
+  /// Add a byte to the end, unless there's no room for it.
+  fn push(&mut self, byte: u8) -> Result<(), DecodeError>;
+
+  /// The bytes so far.
+  fn bytes_mut(&mut self) -> &mut [u8];
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Output for Vec<u8> {
+  fn push(&mut self, byte: u8) -> Result<(), DecodeError> {
+    Vec::push(self, byte);
+    Ok(())
+  }
+
+  fn bytes_mut(&mut self) -> &mut [u8] {
+    self
+  }
+}
+
+/// Decodes into an `[u8; N]`, which has to end up exactly full.
+pub struct ArrayOutput<const N: usize> {
+  bytes: [u8; N],
+  len: usize,
+}
+
+impl<const N: usize> ArrayOutput<N> {
+  pub fn new() -> Self {
+    ArrayOutput {
+      bytes: [0; N],
+      len: 0,
+    }
+  }
+
+  pub fn finish(self) -> Result<[u8; N], DecodeError> {
+    if self.len == N {
+      Ok(self.bytes)
+    } else {
+      Err(DecodeError::invalid_length())
+    }
+  }
+}
+
+impl<const N: usize> Default for ArrayOutput<N> {
+  fn default() -> Self {
+    ArrayOutput::new()
+  }
+}
+
+impl<const N: usize> Output for ArrayOutput<N> {
+  fn push(&mut self, byte: u8) -> Result<(), DecodeError> {
+    let slot = self.bytes.get_mut(self.len).ok_or_else(DecodeError::invalid_length)?;
+
+    *slot = byte;
+    self.len += 1;
+    Ok(())
+  }
+
+  fn bytes_mut(&mut self) -> &mut [u8] {
+    &mut self.bytes[..self.len]
+  }
+}
+
+pub fn encode(bytes: &[u8], encoding: Encoding, f: &mut fmt::Formatter) -> fmt::Result {
+  match encoding {
+    Encoding::Hex => {
+      for &byte in bytes {
+        write_digit(f, HEX[usize::from(byte >> 4)])?;
+        write_digit(f, HEX[usize::from(byte & 0xf)])?;
+      }
+
+      Ok(())
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Encoding::Base58 => encode_base58(bytes, f),
+    Encoding::Base64 => {
+      for chunk in bytes.chunks(3) {
+        let group = chunk
+          .iter()
+          .enumerate()
+          .fold(0u32, |group, (i, &byte)| group | u32::from(byte) << (16 - 8 * i));
+
+        for i in 0..4 {
+          if i <= chunk.len() {
+            write_digit(f, BASE64[(group >> (18 - 6 * i) & 0x3f) as usize])?;
+          } else {
+            f.write_str("=")?;
+          }
+        }
+      }
+
+      Ok(())
+    }
+  }
+}
+
+pub fn decode<O: Output>(input: &str, encoding: Encoding, out: &mut O) -> Result<(), DecodeError> {
+  match encoding {
+    Encoding::Hex => {
+      let mut digits = input.chars().map(|c| {
+        c.to_digit(16)
+          .map(|digit| digit as u8)
+          .ok_or_else(|| DecodeError::invalid_character(c))
+      });
+      while let Some(high) = digits.next() {
+        let low = digits.next().ok_or_else(DecodeError::invalid_length)?;
+        out.push(high? << 4 | low?)?;
+      }
+
+      Ok(())
+    }
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    Encoding::Base58 => decode_base58(input, out),
+    Encoding::Base64 => {
+      let unpadded = input.trim_end_matches('=');
+
+      let mut group = 0u32;
+      let mut bits = 0;
+      for c in unpadded.chars() {
+        let digit = BASE64
+          .iter()
+          .position(|&digit| char::from(digit) == c)
+          .ok_or_else(|| DecodeError::invalid_character(c))?;
+
+        group = group << 6 | digit as u32;
+        bits += 6;
+        if bits >= 8 {
+          bits -= 8;
+          out.push((group >> bits) as u8)?;
+        }
+      }
+
+      // Each `=` stands in for two bits that didn't make a whole byte, and
+      // those bits should be zeros.
+      let padding = input.len() - unpadded.len();
+      if bits == 6 || padding != bits / 2 || group & ((1 << bits) - 1) != 0 {
+        return Err(DecodeError::invalid_length());
+      }
+
+      Ok(())
+    }
+  }
+}
+
+fn write_digit(f: &mut fmt::Formatter, digit: u8) -> fmt::Result {
+  fmt::Write::write_char(f, char::from(digit))
+}
+
+/// Treat the bytes as one big-endian number and write it out in base 58,
+/// with a `1` for each leading zero byte.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn encode_base58(bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+  let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+
+  // Little-endian base 58 digits, built up a byte at a time.
+  let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+  for &byte in &bytes[zeros..] {
+    let mut carry = u32::from(byte);
+    for digit in &mut digits {
+      carry += u32::from(*digit) << 8;
+      *digit = (carry % 58) as u8;
+      carry /= 58;
+    }
+    while carry > 0 {
+      digits.push((carry % 58) as u8);
+      carry /= 58;
+    }
+  }
+
+  for _ in 0..zeros {
+    f.write_str("1")?;
+  }
+  for &digit in digits.iter().rev() {
+    write_digit(f, BASE58[usize::from(digit)])?;
+  }
+
+  Ok(())
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn decode_base58<O: Output>(input: &str, out: &mut O) -> Result<(), DecodeError> {
+  let zeros = input.chars().take_while(|&c| c == '1').count();
+
+  // Same as above, backwards: the bytes are built up little-endian in the
+  // output, and turned around at the end.
+  for c in input[zeros..].chars() {
+    let digit = BASE58
+      .iter()
+      .position(|&digit| char::from(digit) == c)
+      .ok_or_else(|| DecodeError::invalid_character(c))?;
+
+    let mut carry = digit as u32;
+    for byte in out.bytes_mut().iter_mut() {
+      carry += u32::from(*byte) * 58;
+      *byte = carry as u8;
+      carry >>= 8;
+    }
+    while carry > 0 {
+      out.push(carry as u8)?;
+      carry >>= 8;
+    }
+  }
+
+  for _ in 0..zeros {
+    out.push(0)?;
+  }
+  out.bytes_mut().reverse();
+
+  Ok(())
+}
@@ -31,12 +31,363 @@
 //! * `fn map<F, T>(self, mut f: F) -> T where F: FnMut(InnerType) -> T`
 //! * `fn map_ref<F, T>(&self, mut f: F) -> T where F: FnMut(&InnerType) -> T`
 //! * `fn map_mut<F, T>(&mut self, mut f: F) -> T where F: FnMut(&mut InnerType) -> T`
+//! * `fn transform<F>(self, f: F) -> Self where F: FnOnce(InnerType) -> InnerType`
 //!
-//! `map_mut()` will have the same visibility as the inner field, which ensures
-//! that `map_mut()` doesn't leak the possibility of changing the inner value
-//! (potentially in invariant-violating ways). `map()` and `map_ref()` have the
-//! same visibility as the struct itself, since these *don't* provide direct
-//! ways for callers to break your data.
+//! If all the fields other than the main field are `PhantomData`s, it
+//! will also derive
+//!
+//! * `fn transform_ref<F>(&self, f: F) -> Self where F: FnOnce(&InnerType) -> InnerType`
+//!
+//! `map_mut()`, `transform()` and `transform_ref()` will have the same
+//! visibility as the inner field, which ensures that they don't leak the
+//! possibility of changing the inner value (potentially in invariant-violating
+//! ways). Similarly, the `wrap_*()`
+//! methods have the same visibility as the inner field, since they let
+//! callers create arbitrary instances of the struct. `map()`, `map_ref()`
+//! and the `unwrap_*()` methods have the same visibility as the struct
+//! itself, since these *don't* provide direct ways for callers to break your
+//! data.
+//!
+//! `into_inner()` and `as_inner()` come from the [`Shrinkwrap`](trait.Shrinkwrap.html)
+//! trait. With `#[shrinkwrap(accessors)]`, the struct also gets public
+//! inherent versions of them, for calling without importing the trait:
+//!
+//! * `fn into_inner(self) -> InnerType`
+//! * `fn as_inner(&self) -> &InnerType`
+//!
+//! They're opt-in because they'd clash with an `into_inner()` or `as_inner()`
+//! of the struct's own.
+//!
+//! For converting the values inside `Option`s and `Result`s without a
+//! closure, `#[shrinkwrap(option_result_helpers)]` adds
+//!
+//! * `fn unwrap_opt(wrapped: Option<Self>) -> Option<InnerType>`
+//! * `fn unwrap_result<E>(wrapped: Result<Self, E>) -> Result<InnerType, E>`
+//!
+//! and, if all the fields other than the main field are `PhantomData`s,
+//!
+//! * `fn wrap_opt(inner: Option<InnerType>) -> Option<Self>`
+//! * `fn wrap_result<E>(inner: Result<InnerType, E>) -> Result<Self, E>`
+//!
+//! `#[shrinkwrap(iter_helpers)]` does the same for every item of an
+//! iterator, with `unwrap_iter()` and, with the same caveat, `wrap_iter()`:
+//!
+//! * `fn unwrap_iter<I>(wrapped: I) -> impl Iterator<Item = InnerType> where I: IntoIterator<Item = Self>`
+//! * `fn wrap_iter<I>(inner: I) -> impl Iterator<Item = Self> where I: IntoIterator<Item = InnerType>`
+//!
+//! `transform()` and `transform_ref()` are what other newtype crates call
+//! `map()` and `map_ref()`: they keep the result inside the wrapper. Here
+//! those names were already taken by the methods that hand the result back
+//! as-is, so they got different ones. If you'd rather have the other
+//! convention, swap them around with `rename`:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(rename(
+//!   map = "map_into",
+//!   map_ref = "map_ref_into",
+//!   transform = "map",
+//!   transform_ref = "map_ref"
+//! ))]
+//! struct Email(String);
+//! ```
+//!
+//! Any `///` docs on the main field are copied onto `new()`, and onto
+//! `into_inner()` and `as_inner()` under `accessors`, so they show up where
+//! callers will look for them.
+//!
+//! When the main field is an `Option<T>`, `#[shrinkwrap(option)]` adds the
+//! `Option` methods that only look at the value: `is_some()`, `is_none()`,
+//! `as_opt()` (which is `as_ref()` under a name that doesn't clash with
+//! `AsRef`), `as_deref()`, `unwrap_or()`, `unwrap_or_else()` and
+//! `unwrap_or_default()`. Like `map()`, they have the visibility of the
+//! struct.
+//!
+//! Similarly, `#[shrinkwrap(lock)]` on a wrapper around a `Mutex<T>` adds
+//! `lock()`, and on one around an `RwLock<T>` adds `read()` and `write()`,
+//! so callers don't have to write `.0.lock()`. `lock()` and `write()` have
+//! the visibility of the inner field, since the guards let callers change
+//! the value inside. For wrappers around `parking_lot`'s locks, enable the
+//! `parking_lot` feature and use `#[shrinkwrap(lock = "parking_lot")]`
+//! instead; the guards then come back without a `LockResult`, and there are
+//! also `lock_mapped()`, `read_mapped()` and `write_mapped()`, which keep
+//! only part of the value locked.
+//!
+//! `#[shrinkwrap(cell)]` does the same for a `RefCell<T>`, with `borrow()`,
+//! `try_borrow()` and `borrow_mut()`, and for a `Cell<T>`, with `get()`,
+//! `set()`, `replace()` and `update()`. Again, everything but `borrow()`,
+//! `try_borrow()` and `get()` has the visibility of the inner field. Since
+//! `copy_get` and `mem_helpers` generate a `get()`, `set()` and `replace()`
+//! of their own, they can't be combined with `cell` on a `Cell`. The
+//! `RefCell` methods take precedence over `Borrow::borrow()` and
+//! `BorrowMut::borrow_mut()` in method calls, so if you'd rather keep those,
+//! rename them with e.g. `#[shrinkwrap(rename(borrow = "read"))]`.
+//!
+//! For lock-free counters and flags, `#[shrinkwrap(atomic)]` on a wrapper
+//! around an `AtomicU64`, `AtomicBool` or any other atomic integer adds
+//! `load()` and `store()`, plus `fetch_add()` and `fetch_sub()` for the
+//! integers, all taking and returning the plain value type. Only `load()`
+//! has the visibility of the struct.
+//!
+//! If you'd rather not implement all of the traits above, list the ones you
+//! do want with e.g. `#[shrinkwrap(traits(AsRef, Borrow))]`, out of `Deref`,
+//! `Borrow`, `AsRef`, `DerefMut`, `BorrowMut` and `AsMut`. The mutable ones
+//! still need `#[shrinkwrap(mutable)]`.
+//!
+//! Alternatively, opt out of individual traits with `no_deref`, `no_borrow`
+//! or `no_as_ref`, e.g. if you consider `Deref` on newtypes an anti-pattern
+//! but still want `AsRef` and `Borrow`. `no_deref` and `no_borrow` also
+//! leave out their mutable counterparts, which can be skipped on their own
+//! with `no_deref_mut`, `no_borrow_mut` and `no_as_mut`.
+//!
+//! `Borrow` promises that the wrapper's `Hash`, `Eq` and `Ord` agree with the
+//! inner value's, which is what lets a `HashMap` keyed by the wrapper be
+//! looked up by the inner value. A derived `Hash`, `Eq` or `Ord` on a struct
+//! with other fields breaks that promise, so shrinkwraprs refuses to derive
+//! `Borrow` alongside one, as long as that derive comes after
+//! `#[derive(Shrinkwrap)]` (derives listed before it, or in the same list,
+//! can't be seen). Implement those traits by hand using just the main field,
+//! or, if the wrapper is meant to compare differently from its inner value,
+//! keep `Deref` and `AsRef` but leave out `Borrow` and `BorrowMut` with
+//! `#[shrinkwrap(no_borrow)]`:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[derive(PartialEq, Eq, Hash)]
+//! #[shrinkwrap(no_borrow)]
+//! struct Revision {
+//!     #[shrinkwrap(main_field)] text: String,
+//!     number: u32
+//! }
+//! ```
+//!
+//! If you're sure, you can also silence the error with
+//! `#[shrinkwrap(unsafe_ignore_borrow_contract)]`.
+//!
+//! There's deliberately no feature for leaving `Deref` out everywhere: Cargo
+//! turns a feature on for every crate in the build once any of them asks for
+//! it, so it would change other crates' newtypes too. Use `no_deref` on each
+//! struct instead.
+//!
+//! To only implement some traits in certain builds, gate them on a `cfg`
+//! predicate with e.g. `#[shrinkwrap(cfg(feature = "std", traits(Borrow)))]`.
+//! This covers every impl of those traits, including the ones added by the
+//! options below.
+//!
+//! The accessors in these impls are marked `#[inline]`, so they can be
+//! inlined across crates. Use `#[shrinkwrap(inline(always))]` to insist, or
+//! `#[shrinkwrap(no_inline)]` to leave the attribute off.
+//!
+//! Like `String` and `PathBuf`, a wrapper can deref further than its inner
+//! type with e.g. `#[shrinkwrap(deref_target = "str")]`, as long as the
+//! inner type derefs to that target. `Borrow` and `AsRef` still go to the
+//! inner type itself.
+//!
+//! Fixed-size buffers like `struct Block([u8; 16])` can take
+//! `#[shrinkwrap(slice)]` to deref to `[u8]` instead of the array, and get
+//! `AsRef<[u8]>` (and `AsMut<[u8]>`, if the wrapper is mutable) on top of
+//! the usual impls, along with `as_slice()` and `as_mut_slice()`. The
+//! latter has the visibility of the inner field, like `map_mut()`.
+//!
+//! For wrappers around smart pointers like `Box<T>` or `Arc<T>`,
+//! `#[shrinkwrap(deref_via_inner)]` saves spelling the target out: `Deref`
+//! goes to whatever the inner type derefs to, so the wrapper behaves like a
+//! pointer to `T` rather than a pointer to the pointer.
+//!
+//! Add `#[shrinkwrap(as_ref_via_inner)]` to do the same for `AsRef` (and
+//! `AsMut`, if the wrapper is mutable), which then refer to what the inner
+//! type points to instead of the inner type itself. A plugin-style
+//! `struct Loaded(Box<dyn Plugin>)` with both options behaves like the
+//! `dyn Plugin` it holds, and can be passed to anything taking
+//! `impl AsRef<dyn Plugin>`. The same goes for wrappers around references:
+//! with both options, `struct Name<'a>(&'a str)` derefs to `str` and
+//! implements `AsRef<str>`, rather than going through a `&&str`.
+//!
+//! When the main field is itself a shrinkwrapped newtype, add
+//! `#[shrinkwrap(transitive)]` to also get `Borrow` and `AsRef` for *its*
+//! inner type, so layered newtypes don't need two conversions everywhere.
+//!
+//! Wrappers around `PathBuf`s or `String`s that hold paths can take
+//! `#[shrinkwrap(path_like)]`, which adds `AsRef<Path>` and `AsRef<OsStr>` so
+//! they can be passed straight to `std::fs` and `std::process`. This needs
+//! the `std` feature.
+//!
+//! Similarly, `#[shrinkwrap(bytes)]` adds `AsRef<[u8]>` for wrappers over
+//! `Vec<u8>`, `String` or `[u8; N]`, plus `AsMut<[u8]>` when the wrapper is
+//! mutable and the inner type allows it (`String` doesn't, since that would
+//! let you write invalid UTF-8).
+//!
+//! Or, with `#[shrinkwrap(forward_as_ref)]`, the wrapper gets every `AsRef`
+//! its inner type has, through one blanket impl. That replaces the usual
+//! `AsRef<InnerType>`, so it's only there if the inner type has it too
+//! (`String` doesn't, for instance), and it can't be combined with the other
+//! options that add `AsRef` impls.
+//!
+//! `#[shrinkwrap(forward_borrow)]` does the same for `Borrow`, which makes
+//! wrapped keys in a `HashMap` or `BTreeMap` look up by anything their inner
+//! value borrows as. Since `Borrow` promises that borrowed values compare and
+//! hash the same as the originals, this also generates `PartialEq`, `Eq` and
+//! `Hash` impls that forward to the inner value (plus `PartialOrd` and `Ord`
+//! if it has them), so don't derive those yourself. The inner type can't be
+//! a bare type parameter, since that would overlap with the standard
+//! library's `impl<T> Borrow<T> for T`.
+//!
+//! With the `equivalent` feature enabled, `#[shrinkwrap(equivalent)]`
+//! implements `Equivalent` from the crate of the same name, which `hashbrown`
+//! and `indexmap` use for lookups. `Borrow` already covers looking keys up by
+//! their inner value, so this adds the lookups it doesn't: by the inner value
+//! when `Borrow` is opted out of, and by `str` when the inner type is `String`.
+//!
+//! Likewise, the `uuid` feature allows `#[shrinkwrap(uuid)]` on wrappers
+//! around a `Uuid`, for `new_v4()`, `nil()` and `parse_str()` constructors
+//! that return the wrapper, and `as_bytes()`. The constructors have the
+//! visibility of the inner field, like `wrap_opt()`.
+//!
+//! Hashes and other byte-array IDs can take
+//! `#[shrinkwrap(display_encoding = "hex")]` (or `"base58"` or `"base64"`)
+//! to implement `Display` and `FromStr` in that encoding. The main field has
+//! to be an `[u8; N]` or a `Vec<u8>`, and strings that don't decode, or
+//! decode to the wrong number of bytes for an array, fail to parse with a
+//! `shrinkwraprs::DecodeError`. Base58 and `Vec<u8>` need the `std` or
+//! `alloc` feature.
+//!
+//! Two wrappers can also be linked into an owned/borrowed pair, like `PathBuf`
+//! and `Path`. The borrowed half wraps something unsized and has to be
+//! `#[repr(transparent)]`; the owned half then derefs to it, borrows as it,
+//! and is what it turns into with `to_owned()`:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(owned = "EmailBuf")]
+//! #[repr(transparent)]
+//! struct Email(str);
+//!
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(borrowed = "Email")]
+//! struct EmailBuf(String);
+//! ```
+//!
+//! Since it can't be moved around, the borrowed half only gets `Deref`,
+//! `Borrow`, `AsRef` and a `from_ref()` for casting references to the inner
+//! value. The owned half doesn't get `DerefMut`. Pairs can be generic, but
+//! since `ToOwned` is implemented on the borrowed half, any generic parameter
+//! of the owned half that the borrowed half doesn't mention needs a default,
+//! which is what `to_owned()` builds.
+//!
+//! A `#[repr(transparent)]` struct can wrap something unsized without being
+//! half of a pair, too, like `struct Label(str)` or `struct Bytes([u8])`. It
+//! gets the same `Deref`, `Borrow`, `AsRef` and `from_ref()`, plus
+//! `from_mut()`, but not the `Shrinkwrap` trait, which needs a sized inner
+//! type. `str`, slices and trait objects are recognized as unsized; for
+//! other unsized types, like `Path`, add `#[shrinkwrap(unsized_inner)]`.
+//!
+//! If `into_inner()` clashes with a method you already have, or doesn't fit
+//! your naming conventions, you can rename it:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(accessors, rename(into_inner = "into_raw", as_inner = "raw"))]
+//! struct Email(String);
+//! ```
+//!
+//! The same goes for `as_inner`, `as_inner_mut`, `inner_mut`, `get`, `new`,
+//! `set`, `replace`, `take`, `swap`, `map`, `map_ref`, `map_mut`, `transform`,
+//! `transform_ref`, `into_parts`, `wrap_opt`, `wrap_result`, `unwrap_opt`,
+//! `unwrap_result`, `wrap_iter`, `unwrap_iter`, `update`, `borrow`,
+//! `try_borrow` and `borrow_mut`.
+//!
+//! Adding `#[shrinkwrap(constructor)]` will generate a constructor,
+//! `fn new(inner: InnerType) -> Self`, which fills in any other fields with
+//! their `Default` values. The constructor is public by default; if you'd
+//! like to keep construction private while still exposing read access, give
+//! it a different visibility with e.g.
+//! `#[shrinkwrap(constructor_vis = "pub(crate)")]`.
+//!
+//! With `#[shrinkwrap(constructor_into)]`, the constructor will instead take
+//! anything that converts into the inner type, so that e.g. `Email::new("...")`
+//! works for a `String` wrapper without an explicit `.into()`.
+//!
+//! If you'd like to build wrappers in const contexts, add
+//! `#[shrinkwrap(const_fns)]` to make `new()`, and under `accessors`
+//! `into_inner()` and `as_inner()`, const fns.
+//! The constructor can only be const if all the other fields are
+//! `PhantomData`s, and `into_inner()` can only be const if the struct doesn't
+//! need dropping, e.g. because the inner type is `Copy`. With other fields
+//! besides `PhantomData`s, `into_inner()` is left as a plain fn, since
+//! moving the main field out drops the rest.
+//!
+//! For structs with other fields besides the inner one, there's also
+//! `fn into_parts(self) -> (FieldTypes...)`, which moves every field out at
+//! once as a tuple in declaration order. It's only as visible as the least
+//! visible field. If every field is as visible as the struct, the same tuple
+//! is also available through `From`, so `let (start, end, text) =
+//! span.into();` works too.
+//! `#[shrinkwrap(with_fields)]` generates a builder-style setter
+//! `fn with_fieldname(self, value: FieldType) -> Self` for each of them
+//! (skipping `PhantomData`s), with the same visibility as the field.
+//! Combined with `constructor`, this lets code outside the module build
+//! wrappers with metadata without a struct literal:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(constructor, with_fields)]
+//! pub struct Token {
+//!     #[shrinkwrap(main_field)] text: String,
+//!     pub line: usize,
+//! }
+//!
+//! let token = Token::new("impl".into()).with_line(3);
+//! ```
+//!
+//! A field marked `#[shrinkwrap(rename = "line")]` gets `with_line()` instead,
+//! whatever the field itself is called.
+//!
+//! `Deref` only reaches methods that take `&self`, so the inner type's
+//! constructors aren't available on the wrapper. You can forward to them
+//! with `#[shrinkwrap(delegate_constructors(..))]`, which generates
+//! associated functions that call the inner type's function and wrap the
+//! result. Since procedural macros can't look up the inner type's
+//! signatures, any arguments have to be spelled out; a bare name means
+//! the function takes no arguments:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(delegate_constructors("new", "with_capacity(capacity: usize)"))]
+//! struct Buffer(Vec<u8>);
+//!
+//! let buffer = Buffer::with_capacity(16);
+//! ```
+//!
+//! The delegated constructors have the same visibility as the inner field,
+//! and every other field has to be a `PhantomData`.
+//!
+//! Likewise, methods that take the inner value by `self` can be forwarded
+//! with e.g. `#[shrinkwrap(delegate_owned("into_bytes(self) -> Vec<u8>"))]`.
+//! These unwrap the wrapper and return whatever the inner method returns,
+//! except that methods returning the inner type (or `Self`) get their result
+//! rewrapped; those have the same visibility as the inner field, and the
+//! rest are public.
+//!
+//! `#[shrinkwrap(ext_trait)]` generates an extension trait for the inner
+//! type, so that call sites can wrap values fluently, e.g.
+//! `raw_string.into_email()` for `trait IntoEmail { fn into_email(self) -> Email; }`.
+//! The trait has the same visibility as the inner field, and both names can
+//! be changed with
+//! `#[shrinkwrap(ext_trait(name = "ToEmail", method = "to_email"))]`. Since
+//! the trait only gets the inner value, every other field has to be a
+//! `PhantomData`.
+//!
+//! For newtypes that validate or otherwise mean something, you'll probably
+//! want a warning whenever a freshly built wrapper gets thrown away.
+//! `#[shrinkwrap(must_use)]` marks the generated constructors, `map()`s and
+//! conversion methods like `into_inner()` with `#[must_use]`.
+//!
+//! For small `Copy` inner types like `u64`, `#[shrinkwrap(copy_get)]` adds
+//! `fn get(&self) -> InnerType`, which returns the inner value by copy, so
+//! comparisons like `id.get() > 5` don't need a dereference. It's const
+//! along with the other accessors under `const_fns`. It takes `&self` so
+//! that calling it doesn't move a wrapper that isn't `Copy` itself, and it
+//! has an `InnerType: Copy` bound, so it can't be called otherwise.
 //!
 //! Additionally, using `#[shrinkwrap(mutable)]` will also
 //! derive the following traits:
@@ -45,6 +396,22 @@
 //! * `BorrowMut<InnerType>`
 //! * `DerefMut<Target=InnerType>`
 //!
+//! Under `accessors`, mutable shrinkwraps also get
+//! `fn as_inner_mut(&mut self) -> &mut InnerType`, which has the same
+//! visibility as the inner field.
+//!
+//! Adding `#[shrinkwrap(mem_helpers)]` as well generates the following, with
+//! the same visibility:
+//!
+//! * `fn set(&mut self, new: InnerType)`
+//! * `fn replace(&mut self, new: InnerType) -> InnerType`
+//! * `fn take(&mut self) -> InnerType where InnerType: Default`
+//! * `fn swap(&mut self, other: &mut Self)`
+//!
+//! They're opt-in because they'd hide methods of the same name on the inner
+//! type, like `str::replace()` or `<[T]>::swap()`, which are otherwise
+//! reached through `DerefMut`.
+//!
 //! ## Cool, how do I use it?
 //!
 //! ```ignore
@@ -63,6 +430,54 @@
 //! }
 //! ```
 //!
+//! On the 2018 edition or later, you can import the derives (and the
+//! traits they implement) with `use shrinkwraprs::prelude::*;` instead of
+//! `#[macro_use] extern crate shrinkwraprs;`.
+//!
+//! If you'd rather not spell out the derive, `#[shrinkwrap(..)]` also works
+//! as an attribute on its own. It adds `#[derive(Shrinkwrap)]` for you, and
+//! with `transparent`, the `#[repr(transparent)]` that goes with it:
+//!
+//! ```ignore
+//! #[shrinkwrap(mutable, transparent)]
+//! struct Email(String);
+//! ```
+//!
+//! Next to a derive, the attribute has to come after it, like the options
+//! always have.
+//!
+//! For crates with lots of simple wrappers, `shrinkwrap_newtypes!` declares
+//! several at once, and shares any options in the `#![shrinkwrap(..)]` at the
+//! top between them. Each struct can still have attributes of its own:
+//!
+//! ```ignore
+//! shrinkwrap_newtypes! {
+//!     #![shrinkwrap(constructor, copy_get)]
+//!
+//!     pub struct Width(u64);
+//!     pub struct Height(u64);
+//!
+//!     #[shrinkwrap(mutable)]
+//!     pub struct Depth(u64);
+//! }
+//! ```
+//!
+//! For wrappers that only accept some inner values, `newtype!` writes the
+//! struct, the derive and a checked `try_new()` (plus `TryFrom`) in one go.
+//! Values that fail the check are handed back as the error:
+//!
+//! ```ignore
+//! newtype! {
+//!     pub struct Port(u16) where |port| (1024..=65535).contains(port);
+//! }
+//!
+//! assert!(Port::try_new(8080).is_ok());
+//! assert_eq!(Port::try_new(80).err(), Some(80));
+//! ```
+//!
+//! Writing through `DerefMut` or `set()` would skip the check, so these
+//! structs can't be `mutable`.
+//!
 //! If you have multiple fields, but there's only one field you want to be able
 //! to deref/borrow as, mark it with `#[shrinkwrap(main_field)]`:
 //!
@@ -77,6 +492,85 @@
 //! struct CodeSpan(u32, u32, #[shrinkwrap(main_field)] Token);
 //! ```
 //!
+//! Alternatively, mark the fields that *aren't* interesting with
+//! `#[shrinkwrap(ignore)]`. If that leaves just one field, it becomes the
+//! main field without needing to be marked. `PhantomData` fields are always
+//! ignored this way, so phantom-tagged wrappers like
+//! `struct Length<Unit>(f64, PhantomData<Unit>)` work as they are:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! struct Interned {
+//!     #[shrinkwrap(ignore)] id: u32,
+//!     text: String
+//! }
+//! ```
+//!
+//! You can also pick the main field on the struct itself, by name or by
+//! position, which comes in handy when the struct definition comes from
+//! another macro that doesn't pass field attributes along:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(main_field = "addr")]
+//! struct Email {
+//!     spamminess: f64,
+//!     addr: String
+//! }
+//!
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(main_field = 2)]
+//! struct CodeSpan(u32, u32, Token);
+//! ```
+//!
+//! Any of these options, on the struct or its fields, can be made conditional
+//! with `cfg_attr`, like `#[cfg_attr(feature = "serde", shrinkwrap(ignore))]`.
+//! The compiler expands those before running the derive, so it sees exactly
+//! the options that apply to the current build.
+//!
+//! The same goes for fields behind `#[cfg(..)]`: when they're disabled, the
+//! derive never sees them, so nothing it generates refers to them. That does
+//! mean a struct that has just one field in some builds has its main field
+//! inferred in those builds only, so mark the main field explicitly when the
+//! other fields come and go.
+//!
+//! The main field can even be nested inside one of the struct's fields, by
+//! giving a path like `"config.addr"`. We can't look up the type at the end
+//! of the path, though, so you'll need to spell it out with `main_type`:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(main_field = "config.addr", main_type = "String")]
+//! struct Server {
+//!     name: String,
+//!     config: Config
+//! }
+//! ```
+//!
+//! Since there's no way to build the rest of `config` out of just an
+//! address, nested main fields don't get a constructor, `into_parts()` or
+//! any of the `wrap_*()` methods.
+//!
+//! `Deref` only ever goes to the main field, but other fields can be marked
+//! `#[shrinkwrap(borrow)]` to get `Borrow` and `AsRef` impls of their own, so
+//! the wrapper can be viewed as any of its components:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! struct Token {
+//!     #[shrinkwrap(main_field)] text: String,
+//!     #[shrinkwrap(borrow)] span: Span
+//! }
+//! ```
+//!
+//! Field options can be combined in one attribute, like
+//! `#[shrinkwrap(borrow, rename = "span")]`, and flags can also be spelled
+//! out as `#[shrinkwrap(borrow = true)]`, which is handy for generated code.
+//!
+//! Or, to get `AsRef` for every field without marking each one, put
+//! `#[shrinkwrap(as_ref_all)]` on the struct. Fields that share their type
+//! with another field are skipped, since the impls would overlap.
+//!
 //! If you also want to be able to modify the wrapped value directly,
 //! add the attribute `#[shrinkwrap(mutable)]` as well:
 //!
@@ -92,198 +586,334 @@
 //! input_buffer.push_str("some values");
 //! ...
 //! ```
-
-// Additionally, perhaps subsume some functionality from
-// [`from_variants`](https://crates.io/crates/from_variants)?
+//!
+//! Since mutable access lets code change the inner value however it likes,
+//! the mutable traits are only derived if the inner field is at least as
+//! visible as the struct. Sometimes shrinkwraprs can't tell, like with a
+//! struct that's `pub(in ::a::b)` and a field that's `pub(super)`; that's an
+//! error by default, but `#[shrinkwrap(visibility_policy = "warn")]` or
+//! `"allow"` derives them anyway, with or without a warning. Warnings need
+//! the `nightly-diagnostics` feature, which also makes `"warn"` the default.
+//!
+//! If exposing a less visible field is intentional, because the struct has no
+//! invariants to protect, `#[shrinkwrap(unsafe_ignore_visibility)]` skips the
+//! check altogether:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(mutable, unsafe_ignore_visibility)]
+//! pub struct Scratch(Vec<u8>);
+//! ```
+//!
+//! Alternatively, you can derive `ShrinkwrapMut` alongside `Shrinkwrap`,
+//! which generates the same mutable traits. This does mean parsing and
+//! validating the struct twice, though, so for crates with lots of
+//! newtypes, `#[shrinkwrap(mutable)]` is the cheaper of the two:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap, ShrinkwrapMut)]
+//! struct InputBuffer {
+//!     buffer: String
+//! }
+//! ```
+//!
+//! `ShrinkwrapMut` on its own doesn't work, since the mutable traits build on
+//! the immutable ones; forgetting `Shrinkwrap` gives you a single error at
+//! the struct, mentioning `derive_Shrinkwrap_along_with_ShrinkwrapMut`.
+//!
+//! To route writes somewhere other than reads, mark a second field of the
+//! same type with `#[shrinkwrap(main_field_mut)]`; the mutable traits and
+//! methods will then point at that field instead of the main field. A field
+//! of some other type is an error, since writes go through the same
+//! conversions as reads:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(mutable)]
+//! struct Draft {
+//!     #[shrinkwrap(main_field)] published: String,
+//!     #[shrinkwrap(main_field_mut)] pending: String
+//! }
+//! ```
+//!
+//! Or, to have no mutable field at all, put
+//! `#[shrinkwrap(main_field_mut = "none")]` on the struct. It then gets none
+//! of the mutable traits or methods, even if `mutable` is among the options
+//! `shrinkwrap_newtypes!` shares with it, and `#[derive(ShrinkwrapMut)]` is
+//! an error.
+//!
+//! However you derive them, the mutable traits can be left out
+//! individually. `DerefMut` is the easiest of them to accidentally break
+//! invariants through, so if your codebase bans it, you can keep just
+//! `AsMut` and `BorrowMut`:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap, ShrinkwrapMut)]
+//! #[shrinkwrap(no_deref_mut)]
+//! struct InputBuffer {
+//!     buffer: String
+//! }
+//! ```
+//!
+//! Trait impls are always as visible as the trait, so they can't give mutable
+//! access to just your own crate. For that, use
+//! `#[shrinkwrap(mut_access = "pub(crate)")]` instead of
+//! `#[shrinkwrap(mutable)]`, which generates
+//! `fn inner_mut(&mut self) -> &mut InnerType` with the given visibility and
+//! no mutable traits at all:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(mut_access = "pub(crate)")]
+//! pub struct Balance(u64);
+//!
+//! *balance.inner_mut() += 100;
+//! ```
+//!
+//! If your struct is `#[repr(transparent)]`, you can add
+//! `#[shrinkwrap(transparent)]` to also get methods for viewing borrowed
+//! inner values as the wrapper, without any copying:
+//!
+//! ```ignore
+//! #[derive(Shrinkwrap)]
+//! #[shrinkwrap(transparent)]
+//! #[repr(transparent)]
+//! struct Email(String);
+//!
+//! fn as_email(raw: &String) -> &Email {
+//!     Email::from_ref(raw)
+//! }
+//! ```
+//!
+//! * `fn from_ref(inner: &InnerType) -> &Self`
+//! * `fn from_mut(inner: &mut InnerType) -> &mut Self`
+//! * `fn as_ptr(&self) -> *const InnerType`
+//! * `fn as_mut_ptr(&mut self) -> *mut InnerType`
+//! * `fn from_slice(inner: &[InnerType]) -> &[Self]`
+//! * `fn from_slice_mut(inner: &mut [InnerType]) -> &mut [Self]`
+//! * `fn as_inner_slice(wrapped: &[Self]) -> &[InnerType]`
+//! * `fn as_inner_slice_mut(wrapped: &mut [Self]) -> &mut [InnerType]`
+//! * `fn wrap_array<const N: usize>(inner: [InnerType; N]) -> [Self; N]`
+//! * `fn unwrap_array<const N: usize>(wrapped: [Self; N]) -> [InnerType; N]`
+//! * `from_array_ref()`/`from_array_mut()` and
+//!   `as_inner_array()`/`as_inner_array_mut()`, which do the same as the
+//!   slice versions for references to arrays
+//! * `fn wrap_vec(inner: Vec<InnerType>) -> Vec<Self>` (with the `std` or `alloc` feature)
+//! * `fn unwrap_vec(wrapped: Vec<Self>) -> Vec<InnerType>` (with the `std` or `alloc` feature)
+//! * `fn from_boxed(inner: Box<InnerType>) -> Box<Self>` (with the `std` or `alloc` feature)
+//! * `fn unwrap_boxed(wrapped: Box<Self>) -> Box<InnerType>` (with the `std` or `alloc` feature)
+//! * `from_rc()`/`unwrap_rc()` and `from_arc()`/`unwrap_arc()`, which do the
+//!   same for `Rc` and `Arc` (with the `std` or `alloc` feature)
+//! * `fn into_raw(wrapped: Box<Self>) -> *mut InnerType` (with the `std` or `alloc` feature)
+//! * `unsafe fn from_raw(ptr: *mut InnerType) -> Box<Self>` (with the `std` or `alloc` feature)
+//!
+//! Since most of these let anyone turn an arbitrary inner value into the
+//! wrapper or modify the inner value, they have the same visibility as the
+//! inner field; `as_ptr()`, `as_inner_slice()`, `as_inner_array()`,
+//! `into_raw()` and the `unwrap_*()` methods have the same visibility as the
+//! struct.
+//!
+//! For newtypes that are meant to cost nothing at runtime, whether or not
+//! they're `#[repr(transparent)]`, `#[shrinkwrap(assert_zero_cost)]` adds
+//! compile-time checks that the wrapper has the same size and alignment as
+//! its inner value. That way, a refactor that sneaks in another field fails
+//! the build instead of quietly making the wrapper bigger. It only works on
+//! structs without generic parameters.
+//!
+//! Similarly, `#[shrinkwrap(assert_send_sync)]` checks at compile time that
+//! the wrapper is `Send` whenever its inner value is, and the same for
+//! `Sync`, so adding something like an `Rc` or a `Cell` in another field
+//! doesn't quietly make the wrapper unusable across threads.
+//!
+//! `#[shrinkwrap(gen_tests)]` gives a wrapper some tests for free: it emits
+//! a `#[cfg(test)]` module that wraps a sample value, and checks that it
+//! comes back unchanged out of `into_inner()`, `as_inner()` and whichever of
+//! `Deref`, `Borrow`, `AsRef`, `as_inner_mut()` and `from_ref()` the struct
+//! has. The sample is the inner type's `Default` unless you pass one, like
+//! `#[shrinkwrap(gen_tests = "String::from(\"hi\")")]`. Other fields are
+//! filled in like `new()` does, and the inner type needs `PartialEq`. It
+//! only works on structs without generic parameters.
+//!
+//! With the `std` feature, `#[shrinkwrap(cow)]` derives `From<Wrapper>` and
+//! `From<&Wrapper>` for `Cow<InnerType>`, for APIs that accept either owned
+//! or borrowed values. The inner type needs to be `Clone`. If all the other
+//! fields are `PhantomData`s, it will also generate
+//! `fn from_cow(inner: Cow<InnerType>) -> Self`, which has the same
+//! visibility as the inner field.
+//!
+//! ## Standard library paths
+//!
+//! shrinkwraprs works in `#![no_std]` crates: turn off the default `std`
+//! feature, and generated code refers to `::core` instead. Things that need
+//! an allocator, like `#[shrinkwrap(cow)]` and `wrap_vec()`, are left out,
+//! unless you enable the `alloc` feature and add `extern crate alloc;` to
+//! your crate, in which case they come from `::alloc`.
+//!
+//! Generated code refers to the standard library by absolute paths like
+//! `::std::ops::Deref`, so it works no matter what's in scope. If your crate
+//! reaches the standard library through a facade instead, or builds with
+//! `#![no_implicit_prelude]` and a re-export, point the derive at it with
+//! `#[shrinkwrap(std_path = "::my_facade")]`. The facade needs to provide
+//! the same modules as `std`.
+//!
+//! ## Warnings on nightly
+//!
+//! Some problems aren't worth failing the build over, but stable Rust doesn't
+//! let derives emit warnings. On nightly, the `nightly-diagnostics` feature
+//! turns them on. shrinkwraprs then warns when a derived `PartialEq` or
+//! `PartialOrd` looks at fields besides the main field, which disagrees with
+//! `Borrow` (see above). It also downgrades the error for mutable traits on a struct whose inner field's
+//! visibility can't be compared with the struct's to a warning, unless the
+//! struct picks a different `visibility_policy`.
+//!
+//! ## Seeing the generated code
+//!
+//! With the `debug-expansions` feature, each derive writes the code it
+//! generated to `$SHRINKWRAP_DEBUG_DIR/<crate>-<Type>.<Derive>.rs`, or under
+//! `shrinkwraprs/` in the system's temporary directory if the variable isn't
+//! set. The output isn't formatted nicely; run `rustfmt` on it to read it.
+//! With `nightly-diagnostics` too, the compiler also prints a note with the
+//! path.
+//! Leave the feature off in normal builds, since writing files from a macro
+//! doesn't play well with incremental compilation.
+//!
+//! ## Generic code over wrappers
+//!
+//! Deriving `Shrinkwrap` also implements the [`Shrinkwrap`](trait.Shrinkwrap.html)
+//! trait, so you can write code that works with any shrinkwrapped type:
+//!
+//! ```ignore
+//! use shrinkwraprs::Shrinkwrap;
+//!
+//! fn inner_len<T>(wrapped: &T) -> usize
+//!   where T: Shrinkwrap<Inner=String>
+//! {
+//!     wrapped.as_inner().len()
+//! }
+//! ```
+//!
+//! Mutable shrinkwraps also implement the `ShrinkwrapMut` trait, which
+//! provides `as_inner_mut()`.
+//!
+//! If you'd rather be explicit at call sites than rely on auto-deref, the
+//! free functions `peel()`, `peel_mut()` and `strip()` do the same as
+//! `as_inner()`, `as_inner_mut()` and `into_inner()`:
+//!
+//! ```ignore
+//! let len = shrinkwraprs::peel(&email).len();
+//! let raw: String = shrinkwraprs::strip(email);
+//! ```
+//!
+//! ## Tagged values without the boilerplate
+//!
+//! If all you need is a value tagged with a marker type, you don't need to
+//! define a struct at all; `Sw<Tag, T>` implements all the traits a derived
+//! (mutable) shrinkwrap would, plus `Clone`, `Eq`, `Hash` and friends whenever
+//! `T` does:
+//!
+//! ```ignore
+//! use shrinkwraprs::Sw;
+//!
+//! enum UserIdTag {}
+//! type UserId = Sw<UserIdTag, u64>;
+//!
+//! let id = UserId::new(42);
+//! ```
 
 #![cfg_attr(feature = "strict", deny(warnings))]
-#![recursion_limit = "128"]
-
-extern crate proc_macro;
-extern crate proc_macro2;
-extern crate syn;
-#[macro_use]
-extern crate quote;
-extern crate itertools;
-#[macro_use]
-extern crate bitflags;
+#![no_std]
 
-use proc_macro2::{Span, TokenStream};
-use quote::ToTokens;
-
-mod ast;
-mod visibility;
+#[cfg(any(feature = "std", feature = "alloc"))]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-#[proc_macro_derive(Shrinkwrap, attributes(shrinkwrap))]
-pub fn shrinkwrap(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
-  use crate::ast::{validate_derive_input, ShrinkwrapFlags};
-  use crate::visibility::field_visibility;
-  use crate::visibility::FieldVisibility::*;
+extern crate shrinkwraprs_derive;
 
-  let input: syn::DeriveInput = syn::parse(tokens).unwrap();
-  let (details, input) = validate_derive_input(input);
+pub use shrinkwraprs_derive::{shrinkwrap, shrinkwrap_newtypes, Shrinkwrap, ShrinkwrapMut};
 
-  let mut tokens = TokenStream::new();
+mod encoding;
+mod newtype;
+mod sw;
 
-  impl_immut_borrows(&details, &input).to_tokens(&mut tokens);
-  impl_map(&details, &input).to_tokens(&mut tokens);
+pub use encoding::DecodeError;
+pub use sw::Sw;
 
-  if details.flags.contains(ShrinkwrapFlags::SW_MUT) {
-    // Make sure that the inner field isn't less visible than the outer struct.
-    if !details.flags.contains(ast::ShrinkwrapFlags::SW_IGNORE_VIS) {
-      match field_visibility(&details.visibility, &input.inner_visibility) {
-        Restricted => panic!(
-          "shrinkwraprs: cowardly refusing to implement mutable
-conversion traits because inner field is less visible
-than shrinkwrapped struct. Implementing mutable traits
-could allow violation of struct invariants. If you'd
-like to override this, use
-#[shrinkwrap(unsafe_ignore_visibility)] on your struct."
-        ),
-        CantDetermine => panic!(
-          "shrinkwraprs: cowardly refusing to implement mutable
-conversion traits because I can't figure out whether
-the inner field is as visible as the shrinkwrapped
-struct or not. This is usually because there is a mix
-of visibilities starting at the crate root and
-visiblities starting at self/super. If you'd like to
-override this, use #[shrinkwrap(unsafe_ignore_visibility)] on
-your struct."
-        ),
-        _ => (),
-      }
-    }
+#[cfg(feature = "equivalent")]
+#[doc(hidden)]
+pub extern crate equivalent;
 
-    impl_mut_borrows(&details, &input).to_tokens(&mut tokens);
-  }
+#[cfg(feature = "uuid")]
+#[doc(hidden)]
+pub extern crate uuid;
 
-  tokens.into()
+/// Everything you need to derive and use shrinkwraps, in one import:
+///
+/// ```ignore
+/// use shrinkwraprs::prelude::*;
+/// ```
+pub mod prelude {
+  pub use super::{shrinkwrap, shrinkwrap_newtypes, Shrinkwrap, ShrinkwrapMut};
 }
 
-// When generating our code, we need to be careful not to leak things into the
-// surrounding code. For example, we don't use imports unless they're inside a
-// scope, because otherwise we'd be inserting invisible imports whenever a user
-// used #[derive(Shrinkwrap)].
-
-fn impl_immut_borrows(
-  details: &ast::StructDetails,
-  input: &ast::Struct,
-) -> proc_macro2::TokenStream {
-  let &ast::StructDetails {
-    ref ident,
-    ref generics,
-    ..
-  } = details;
-  let &ast::Struct {
-    ref inner_field,
-    ref inner_type,
-    ..
-  } = input;
+/// A type that wraps some inner value, which is what `#[derive(Shrinkwrap)]`
+/// implements its conversions to.
+pub trait Shrinkwrap {
+  /// The type of the wrapped value.
+  type Inner;
 
-  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-  let rust = syn::Ident::new(RUST, Span::call_site());
+  /// Unwrap the wrapper, consuming it in the process.
+  fn into_inner(self) -> Self::Inner;
 
-  quote! {
-    impl #impl_generics ::#rust::ops::Deref for #ident #ty_generics #where_clause {
-      type Target = #inner_type;
-      fn deref(&self) -> &Self::Target {
-        &self.#inner_field
-      }
-    }
-
-    impl #impl_generics ::#rust::borrow::Borrow<#inner_type> for #ident #ty_generics #where_clause {
-      fn borrow(&self) -> &#inner_type {
-        &self.#inner_field
-      }
-    }
-
-    impl #impl_generics ::#rust::convert::AsRef<#inner_type> for #ident #ty_generics #where_clause {
-      fn as_ref(&self) -> &#inner_type {
-        &self.#inner_field
-      }
-    }
-  }
+  /// Get a reference to the wrapped value.
+  fn as_inner(&self) -> &Self::Inner;
 }
 
-fn impl_mut_borrows(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
-  let &ast::StructDetails {
-    ref ident,
-    ref generics,
-    ..
-  } = details;
-  let &ast::Struct {
-    ref inner_field,
-    ref inner_type,
-    ..
-  } = input;
-
-  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-  let rust = syn::Ident::new(RUST, Span::call_site());
+/// A wrapper that also allows mutable access to its inner value, which is
+/// implemented by `#[derive(ShrinkwrapMut)]` or `#[shrinkwrap(mutable)]`.
+pub trait ShrinkwrapMut: Shrinkwrap {
+  /// Get a mutable reference to the wrapped value.
+  fn as_inner_mut(&mut self) -> &mut Self::Inner;
+}
 
-  quote! {
-    impl #impl_generics ::#rust::ops::DerefMut for #ident #ty_generics #where_clause {
-      fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.#inner_field
-      }
-    }
+/// Get a reference to the value inside a wrapper. The same as
+/// `Shrinkwrap::as_inner()`, for when you'd rather be explicit than rely on
+/// auto-deref.
+pub fn peel<T: Shrinkwrap>(wrapped: &T) -> &T::Inner {
+  wrapped.as_inner()
+}
 
-    impl #impl_generics ::#rust::borrow::BorrowMut<#inner_type> for #ident #ty_generics #where_clause {
-      fn borrow_mut(&mut self) -> &mut #inner_type {
-        &mut self.#inner_field
-      }
-    }
+/// Get a mutable reference to the value inside a wrapper. The same as
+/// `ShrinkwrapMut::as_inner_mut()`.
+pub fn peel_mut<T: ShrinkwrapMut>(wrapped: &mut T) -> &mut T::Inner {
+  wrapped.as_inner_mut()
+}
 
-    impl #impl_generics ::#rust::convert::AsMut<#inner_type> for #ident #ty_generics #where_clause {
-      fn as_mut(&mut self) -> &mut #inner_type {
-        &mut self.#inner_field
-      }
-    }
-  }
+/// Unwrap a wrapper, consuming it in the process. The same as
+/// `Shrinkwrap::into_inner()`.
+pub fn strip<T: Shrinkwrap>(wrapped: T) -> T::Inner {
+  wrapped.into_inner()
 }
 
-fn impl_map(details: &ast::StructDetails, input: &ast::Struct) -> proc_macro2::TokenStream {
-  let &ast::StructDetails {
-    ref ident,
-    ref generics,
-    ..
-  } = details;
-  let &ast::Struct {
-    ref inner_field,
-    ref inner_type,
-    ref inner_visibility,
-  } = input;
+/// Not public API; only for use by the derives and `newtype!`.
+#[doc(hidden)]
+pub mod __private {
+  // So `newtype!` doesn't depend on what's in scope where it's used.
+  pub use core::convert::TryFrom;
+  pub use core::result::Result::{self, Err, Ok};
 
-  let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+  pub use encoding::{decode, encode, ArrayOutput, Encoding};
 
-  // This is a *massive* hack to avoid variable capture, but I can't figure out
-  // how to get `quote` to enforce hygiene or generate a gensym.
-  let f = quote!(__SHRINKWRAP_F);
-  let t = quote!(__SHRINKWRAP_T);
+  #[cfg(feature = "parking_lot")]
+  pub extern crate parking_lot;
 
-  quote! {
-    #[allow(dead_code, non_camel_case_types)]
-    impl #impl_generics #ident #ty_generics #where_clause {
-      /// Map a function over the wrapped value, consuming it in the process.
-      pub fn map<#t, #f: FnMut(#inner_type) -> #t>(self, mut f: #f) -> #t {
-        f(self.#inner_field)
-      }
+  /// What the owned half of a pair like `PathBuf` and `Path` needs from the
+  /// borrowed half, whichever traits and methods that one ends up with.
+  pub trait BorrowedHalf {
+    type Inner: ?Sized;
 
-      /// Map a function over the wrapped value without consuming it.
-      pub fn map_ref<#t, #f: FnMut(&#inner_type) -> #t>(&self, mut f: #f) -> #t {
-        f(&self.#inner_field)
-      }
+    fn as_inner(&self) -> &Self::Inner;
 
-      /// Map a function over the wrapped value, potentially changing it in place.
-      #inner_visibility fn map_mut<#t, #f>(&mut self, mut f: #f) -> #t
-        where #f: FnMut(&mut #inner_type) -> #t
-      {
-        f(&mut self.#inner_field)
-      }
-    }
+    fn from_inner(inner: &Self::Inner) -> &Self;
   }
 }
-
-#[cfg(feature = "std")]
-const RUST: &str = "std";
-#[cfg(not(feature = "std"))]
-const RUST: &str = "core";
@@ -0,0 +1,58 @@
+//! `newtype!`, for wrappers whose whole point is that not every inner value
+//! is allowed in. It writes the struct, the derive and a checked constructor,
+//! so the check lives right next to the type.
+
+/// Declare newtypes that can only be built out of values that pass a check:
+///
+/// ```ignore
+/// newtype! {
+///     /// A port that doesn't need root to bind to.
+///     pub struct Port(u16) where |port| (1024..=65535).contains(port);
+/// }
+///
+/// let port = Port::try_new(8080).unwrap();
+/// assert_eq!(Port::try_new(80), Err(80));
+/// ```
+///
+/// Each struct derives `Shrinkwrap`, and gets
+/// `fn try_new(inner: InnerType) -> Result<Self, InnerType>` along with the
+/// matching `TryFrom<InnerType>`; a value that fails the check is handed
+/// back. The inner field is private, so outside the struct's module there's
+/// no way around the check. Attributes on the struct, like
+/// `#[shrinkwrap(..)]` options, are kept, except that `mutable`,
+/// `mut_access` and `#[derive(ShrinkwrapMut)]` are refused: writing through
+/// them would skip the check.
+#[macro_export]
+macro_rules! newtype {
+  ($(
+    $(#[$attr:meta])*
+    $vis:vis struct $name:ident($inner:ty) where |$value:ident| $check:expr;
+  )*) => {$(
+    #[derive($crate::Shrinkwrap)]
+    #[shrinkwrap(__checked)]
+    $(#[$attr])*
+    $vis struct $name($inner);
+
+    impl $name {
+      /// Wrap a value if it passes the check, or hand it back if it doesn't.
+      #[allow(dead_code)]
+      $vis fn try_new(inner: $inner) -> $crate::__private::Result<Self, $inner> {
+        let check = |$value: &$inner| -> bool { $check };
+
+        if check(&inner) {
+          $crate::__private::Ok($name(inner))
+        } else {
+          $crate::__private::Err(inner)
+        }
+      }
+    }
+
+    impl $crate::__private::TryFrom<$inner> for $name {
+      type Error = $inner;
+
+      fn try_from(inner: $inner) -> $crate::__private::Result<Self, $inner> {
+        $name::try_new(inner)
+      }
+    }
+  )*};
+}
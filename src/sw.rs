@@ -0,0 +1,154 @@
+//! A ready-made tagged newtype, for when all you want is "a `u64`, but
+//! marked as a user ID" and defining and deriving a whole struct for it
+//! feels like overkill.
+//!
+//! The trait impls in here are written out by hand rather than derived.
+//! `#[derive]` would require the tag type to implement the traits too, and
+//! tags are usually empty types that implement nothing. And the code
+//! `#[derive(Shrinkwrap)]` generates depends on the derive's `std` feature,
+//! which can't be relied upon to match ours.
+
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use {Shrinkwrap, ShrinkwrapMut};
+
+/// A `T` tagged with a marker type `Tag`, so that values with different tags
+/// can't be mixed up:
+///
+/// ```ignore
+/// enum UserIdTag {}
+/// enum PostIdTag {}
+///
+/// type UserId = Sw<UserIdTag, u64>;
+/// type PostId = Sw<PostIdTag, u64>;
+/// ```
+///
+/// `Sw` implements all the traits `#[derive(Shrinkwrap)]` and
+/// `#[shrinkwrap(mutable)]` would, plus the usual value-type traits
+/// whenever `T` implements them, regardless of what `Tag` implements.
+pub struct Sw<Tag: ?Sized, T>(
+  pub T,
+  // `fn() -> Tag` rather than `Tag` so that the tag doesn't affect whether
+  // the wrapper is `Send`/`Sync`, or whether it can be dropped.
+  pub PhantomData<fn() -> Tag>,
+);
+
+impl<Tag: ?Sized, T> Sw<Tag, T> {
+  /// Tag a value.
+  pub fn new(inner: T) -> Self {
+    Sw(inner, PhantomData)
+  }
+}
+
+impl<Tag: ?Sized, T> Shrinkwrap for Sw<Tag, T> {
+  type Inner = T;
+
+  fn into_inner(self) -> T {
+    self.0
+  }
+
+  fn as_inner(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<Tag: ?Sized, T> ShrinkwrapMut for Sw<Tag, T> {
+  fn as_inner_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+}
+
+impl<Tag: ?Sized, T> Deref for Sw<Tag, T> {
+  type Target = T;
+
+  fn deref(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<Tag: ?Sized, T> DerefMut for Sw<Tag, T> {
+  fn deref_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+}
+
+impl<Tag: ?Sized, T> Borrow<T> for Sw<Tag, T> {
+  fn borrow(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<Tag: ?Sized, T> BorrowMut<T> for Sw<Tag, T> {
+  fn borrow_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+}
+
+impl<Tag: ?Sized, T> AsRef<T> for Sw<Tag, T> {
+  fn as_ref(&self) -> &T {
+    &self.0
+  }
+}
+
+impl<Tag: ?Sized, T> AsMut<T> for Sw<Tag, T> {
+  fn as_mut(&mut self) -> &mut T {
+    &mut self.0
+  }
+}
+
+impl<Tag: ?Sized, T: Clone> Clone for Sw<Tag, T> {
+  fn clone(&self) -> Self {
+    Sw::new(self.0.clone())
+  }
+}
+
+impl<Tag: ?Sized, T: Copy> Copy for Sw<Tag, T> {}
+
+impl<Tag: ?Sized, T: Default> Default for Sw<Tag, T> {
+  fn default() -> Self {
+    Sw::new(T::default())
+  }
+}
+
+impl<Tag: ?Sized, T: fmt::Debug> fmt::Debug for Sw<Tag, T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_tuple("Sw").field(&self.0).finish()
+  }
+}
+
+impl<Tag: ?Sized, T: PartialEq> PartialEq for Sw<Tag, T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.0 == other.0
+  }
+}
+
+impl<Tag: ?Sized, T: Eq> Eq for Sw<Tag, T> {}
+
+impl<Tag: ?Sized, T: PartialOrd> PartialOrd for Sw<Tag, T> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    self.0.partial_cmp(&other.0)
+  }
+}
+
+impl<Tag: ?Sized, T: Ord> Ord for Sw<Tag, T> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.cmp(&other.0)
+  }
+}
+
+impl<Tag: ?Sized, T: Hash> Hash for Sw<Tag, T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.0.hash(state)
+  }
+}
+
+impl<Tag: ?Sized, T> From<T> for Sw<Tag, T> {
+  fn from(inner: T) -> Self {
+    Sw::new(inner)
+  }
+}
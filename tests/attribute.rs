@@ -0,0 +1,39 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::borrow::Borrow;
+
+// No derive and no `#[repr(transparent)]`; the attribute adds both.
+#[shrinkwrap(mutable, transparent)]
+struct Email(String);
+
+#[shrinkwrap]
+struct Point {
+  #[shrinkwrap(main_field)]
+  x: i32,
+  y: i32,
+}
+
+// The attribute leaves an existing derive alone.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(traits(Borrow))]
+struct Tag(&'static str);
+
+#[test]
+fn test_attribute_derives() {
+  let mut email = Email("chiya".into());
+  email.push_str("@natsumeya.jp");
+
+  assert_eq!(email.len(), 18);
+  assert_eq!(*Point { x: 3, y: 4 }, 3);
+  assert_eq!(*Borrow::<&str>::borrow(&Tag("new")), "new");
+}
+
+#[test]
+fn test_attribute_adds_repr_transparent() {
+  let raw = String::from("a@b.c");
+
+  assert_eq!(Email::from_ref(&raw).as_str(), "a@b.c");
+}
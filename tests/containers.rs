@@ -0,0 +1,123 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+extern crate core;
+
+use core::marker::PhantomData;
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(option_result_helpers, iter_helpers)]
+struct Email(String);
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(option_result_helpers)]
+struct Length<Unit> {
+  #[shrinkwrap(main_field)]
+  value: f64,
+  unit: PhantomData<Unit>,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(option_result_helpers, iter_helpers)]
+struct CodeSpan(u32, u32, #[shrinkwrap(main_field)] String);
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(
+  option_result_helpers,
+  iter_helpers,
+  rename(
+    wrap_opt = "from_opt",
+    unwrap_opt = "into_opt",
+    wrap_iter = "from_iter",
+    unwrap_iter = "into_iter"
+  )
+)]
+struct Nickname(String);
+
+// Without `option_result_helpers`, the struct is free to have its own.
+#[derive(Shrinkwrap)]
+struct Score(u32);
+
+impl Score {
+  fn wrap_opt(score: u32) -> Option<Self> {
+    if score > 0 {
+      Some(Score(score))
+    } else {
+      None
+    }
+  }
+}
+
+impl Nickname {
+  fn unwrap_opt(self) -> String {
+    self.0
+  }
+}
+
+#[test]
+fn test_option_round_trip() {
+  let email = Email::wrap_opt(Some("a@b.c".into()));
+  assert_eq!(email, Some(Email("a@b.c".into())));
+  assert_eq!(Email::unwrap_opt(email), Some("a@b.c".into()));
+
+  let length: Option<Length<()>> = Length::wrap_opt(None);
+  assert_eq!(length, None);
+}
+
+#[test]
+fn test_own_wrap_opt() {
+  assert!(Score::wrap_opt(0).is_none());
+  assert_eq!(*Score::wrap_opt(3).unwrap(), 3);
+}
+
+#[test]
+fn test_result_round_trip() {
+  let length: Result<Length<()>, &str> = Length::wrap_result(Ok(2.0));
+  assert_eq!(Length::unwrap_result(length), Ok(2.0));
+
+  let length: Result<Length<()>, &str> = Length::wrap_result(Err("nope"));
+  assert_eq!(Length::unwrap_result(length), Err("nope"));
+}
+
+#[test]
+fn test_renamed_option_methods() {
+  let nickname = Nickname::from_opt(Some("chino".into()));
+  assert_eq!(nickname, Some(Nickname("chino".into())));
+  assert_eq!(Nickname::into_opt(nickname), Some("chino".into()));
+  assert_eq!(Nickname("cocoa".into()).unwrap_opt(), "cocoa");
+}
+
+#[test]
+fn test_renamed_iter_methods() {
+  let nicknames: Vec<Nickname> = Nickname::from_iter(vec!["chino".into(), "rize".into()]).collect();
+  let names: Vec<String> = Nickname::into_iter(nicknames).collect();
+
+  assert_eq!(names, vec!["chino".to_string(), "rize".to_string()]);
+}
+
+#[test]
+fn test_multi_unwrap() {
+  let span = Some(CodeSpan(0, 4, "impl".into()));
+
+  assert_eq!(CodeSpan::unwrap_opt(span), Some("impl".into()));
+}
+
+#[test]
+fn test_iter_round_trip() {
+  let raw = vec![String::from("a@b.c"), String::from("d@e.f")];
+
+  let emails: Vec<Email> = Email::wrap_iter(raw.clone()).collect();
+  assert!(emails[1].contains("@e"));
+
+  let unwrapped: Vec<String> = Email::unwrap_iter(emails).collect();
+  assert_eq!(unwrapped, raw);
+}
+
+#[test]
+fn test_multi_unwrap_iter() {
+  let spans = vec![CodeSpan(0, 4, "impl".into()), CodeSpan(5, 8, "Foo".into())];
+
+  let tokens: Vec<String> = CodeSpan::unwrap_iter(spans).collect();
+  assert_eq!(tokens, vec!["impl", "Foo"]);
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "std")]
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::borrow::Cow;
+use std::marker::PhantomData;
+
+#[derive(Clone, Debug, PartialEq)]
+struct Token(&'static str);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cow)]
+struct Keyword(Token);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cow)]
+struct Tagged<T, Tag> {
+  #[shrinkwrap(main_field)]
+  value: T,
+  tag: PhantomData<Tag>,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cow)]
+struct CodeSpan(u32, u32, #[shrinkwrap(main_field)] Token);
+
+#[test]
+fn test_into_owned_cow() {
+  let cow: Cow<Token> = Keyword(Token("impl")).into();
+
+  match cow {
+    Cow::Owned(token) => assert_eq!(token, Token("impl")),
+    Cow::Borrowed(_) => panic!("expected an owned cow"),
+  }
+}
+
+#[test]
+fn test_into_borrowed_cow() {
+  let keyword = Keyword(Token("impl"));
+  let cow: Cow<Token> = (&keyword).into();
+
+  match cow {
+    Cow::Borrowed(token) => assert_eq!(*token, Token("impl")),
+    Cow::Owned(_) => panic!("expected a borrowed cow"),
+  }
+}
+
+#[test]
+fn test_from_cow() {
+  let raw = Token("impl");
+
+  let borrowed = Keyword::from_cow(Cow::Borrowed(&raw));
+  let owned = Keyword::from_cow(Cow::Owned(raw.clone()));
+  assert_eq!(*borrowed, *owned);
+
+  let tagged: Tagged<u32, ()> = Tagged::from_cow(Cow::Owned(3));
+  assert_eq!(*tagged, 3);
+}
+
+#[test]
+fn test_multi_into_cow() {
+  let cow: Cow<Token> = CodeSpan(0, 4, Token("impl")).into();
+
+  assert_eq!(*cow, Token("impl"));
+}
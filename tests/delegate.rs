@@ -0,0 +1,102 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::marker::PhantomData;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_constructors("new", "with_capacity(capacity: usize)"))]
+struct Buffer(Vec<u8>);
+
+struct Counter(usize);
+
+impl Counter {
+  fn counting<I>(items: I) -> Self
+  where
+    I: IntoIterator,
+  {
+    Counter(items.into_iter().count())
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_constructors("counting<I>(items: I) where I: IntoIterator"))]
+struct Total(Counter);
+
+struct Tag;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_constructors("new"))]
+struct Line<T> {
+  #[shrinkwrap(main_field)]
+  text: String,
+  tag: PhantomData<T>,
+}
+
+#[test]
+fn test_delegate_constructors() {
+  let empty = Buffer::new();
+  let sized = Buffer::with_capacity(16);
+
+  assert!(empty.is_empty());
+  assert!(sized.capacity() >= 16);
+}
+
+#[test]
+fn test_delegate_generic_constructor() {
+  let line: Line<Tag> = Line::new();
+
+  assert!(line.is_empty());
+}
+
+#[test]
+fn test_delegate_constructor_with_generics() {
+  let total = Total::counting(vec!["a", "b", "c"]);
+
+  assert_eq!((total.0).0, 3);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_owned(
+  "into_bytes(self) -> Vec<u8>",
+  "into_boxed_str(self) -> Box<str>"
+))]
+struct Email(String);
+
+struct Name(String);
+
+impl Name {
+  fn shouted(self) -> Self {
+    Name(self.0.to_uppercase())
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_owned("shouted(self) -> Self"))]
+struct Nickname(Name);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(delegate_owned("into_iter(self) -> std::vec::IntoIter<u8>"))]
+struct Packet(Vec<u8>);
+
+#[test]
+fn test_delegate_owned() {
+  let email = Email("chiya+snacks@natsumeya.jp".into());
+
+  assert_eq!(email.into_bytes()[0], b'c');
+}
+
+#[test]
+fn test_delegate_owned_rewraps() {
+  let nickname = Nickname(Name("chiya".into())).shouted();
+
+  assert_eq!((nickname.0).0, "CHIYA");
+}
+
+#[test]
+fn test_delegate_owned_trait_method() {
+  let packet = Packet(vec![1, 2, 3]);
+
+  assert_eq!(packet.into_iter().sum::<u8>(), 6);
+}
@@ -0,0 +1,58 @@
+#[macro_use]
+extern crate shrinkwraprs;
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(display_encoding = "hex")]
+struct Digest([u8; 4]);
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(display_encoding = "base64")]
+struct Nonce([u8; 5]);
+
+#[test]
+fn test_hex() {
+  let digest = Digest([0xde, 0xad, 0x00, 0x0f]);
+
+  assert_eq!(digest.to_string(), "dead000f");
+  assert_eq!("dead000f".parse(), Ok(digest));
+  assert_eq!("DEAD000F".parse(), Ok(Digest([0xde, 0xad, 0x00, 0x0f])));
+  assert!("dead00".parse::<Digest>().is_err());
+  assert!("dead000f00".parse::<Digest>().is_err());
+  assert!("deadbeeg".parse::<Digest>().is_err());
+}
+
+#[test]
+fn test_base64() {
+  let nonce = Nonce(*b"hello");
+
+  assert_eq!(nonce.to_string(), "aGVsbG8=");
+  assert_eq!("aGVsbG8=".parse(), Ok(nonce));
+  assert!("aGVsbA==".parse::<Nonce>().is_err());
+  assert!("aGVsbG8".parse::<Nonce>().is_err());
+  assert!("aGVsbG9=".parse::<Nonce>().is_err());
+}
+
+#[test]
+fn test_decode_error() {
+  let error = "zz".parse::<Digest>().unwrap_err();
+
+  assert_eq!(error.to_string(), "invalid character 'z' in encoded bytes");
+}
+
+// Base58 and `Vec<u8>` need an allocator.
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod alloc {
+  #[derive(Shrinkwrap, Debug, PartialEq)]
+  #[shrinkwrap(display_encoding = "base58")]
+  struct Address(Vec<u8>);
+
+  #[test]
+  fn test_base58() {
+    let address = Address(b"\0\0hello world".to_vec());
+
+    assert_eq!(address.to_string(), "11StV1DL6CwTryKyV");
+    assert_eq!("11StV1DL6CwTryKyV".parse(), Ok(address));
+    assert_eq!("".parse(), Ok(Address(vec![])));
+    assert!("0OIl".parse::<Address>().is_err());
+  }
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "equivalent")]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use shrinkwraprs::equivalent::Equivalent;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(equivalent)]
+struct Username(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(equivalent, no_borrow)]
+struct Port(u16);
+
+#[test]
+fn test_str_lookup() {
+  let user = Username("ada".into());
+
+  assert!("ada".equivalent(&user));
+  assert!(!"bob".equivalent(&user));
+  assert!(String::from("ada").equivalent(&user));
+}
+
+#[test]
+fn test_inner_lookup_without_borrow() {
+  assert!(8080u16.equivalent(&Port(8080)));
+  assert!(!80u16.equivalent(&Port(8080)));
+}
@@ -0,0 +1,43 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::marker::PhantomData;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(ext_trait)]
+struct Email(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(ext_trait(name = "ToUserId", method = "to_user_id"))]
+struct UserId(u64);
+
+struct Meters;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(ext_trait)]
+struct Quantity<Unit> {
+  #[shrinkwrap(main_field)]
+  value: f64,
+  unit: PhantomData<Unit>,
+}
+
+#[test]
+fn test_ext_trait() {
+  let email = String::from("chiya+snacks@natsumeya.jp").into_email();
+
+  assert_eq!(*email, "chiya+snacks@natsumeya.jp");
+}
+
+#[test]
+fn test_ext_trait_renamed() {
+  assert_eq!(*7.to_user_id(), 7);
+}
+
+#[test]
+fn test_ext_trait_generic() {
+  let height: Quantity<Meters> = 1.5.into_quantity();
+
+  assert_eq!(*height, 1.5);
+}
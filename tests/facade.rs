@@ -0,0 +1,23 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+/// Stands in for a crate that re-exports the standard library.
+mod facade {
+  pub use std::*;
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(std_path = "::facade", mutable, mem_helpers)]
+struct Name(String);
+
+#[test]
+fn test_std_path() {
+  let mut name = Name("ada".into());
+  name.push_str(" lovelace");
+  name.set("grace".into());
+
+  assert_eq!(AsRef::<String>::as_ref(&name), "grace");
+  assert_eq!(name.take(), "grace");
+}
@@ -0,0 +1,33 @@
+#![allow(dead_code)]
+
+// Everything here gets its tests from `#[shrinkwrap(gen_tests)]`; this file
+// just makes sure they compile and pass for a few different shapes of struct.
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::marker::PhantomData;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(gen_tests)]
+struct Count(u32);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, gen_tests = r#"String::from("chiya@natsumeya.jp")"#)]
+struct Email(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(transparent, gen_tests = "vec![1, 2, 3]")]
+#[repr(transparent)]
+struct Samples(Vec<u8>);
+
+struct Meters;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(gen_tests = "2.5", traits(Borrow, AsRef))]
+struct Length {
+  #[shrinkwrap(main_field)]
+  value: f64,
+  unit: PhantomData<Meters>,
+  label: Option<&'static str>,
+}
@@ -0,0 +1,84 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+/// Nothing from the prelude is in scope here, and there are items named like
+/// the ones generated code uses, so anything that isn't fully qualified or
+/// that leaks out of the generated `const` block fails to compile.
+#[no_implicit_prelude]
+mod shadowed {
+  mod core {}
+
+  struct Option;
+  struct Result;
+  struct FnOnce;
+  struct FnMut;
+  trait Deref {}
+  trait Borrow {}
+  trait AsRef {}
+  trait Shrinkwrap {}
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(
+    mutable,
+    mem_helpers,
+    option_result_helpers,
+    ext_trait(name = "IntoName", method = "into_name")
+  )]
+  pub struct Name(pub ::std::string::String);
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(mutable, copy_get)]
+  pub struct Tagged {
+    #[shrinkwrap(main_field)]
+    pub value: u32,
+    pub tag: &'static str,
+  }
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(constructor, with_fields, cow, bytes, as_ref_all)]
+  pub struct Id {
+    #[shrinkwrap(main_field)]
+    pub raw: ::std::string::String,
+    #[shrinkwrap(borrow)]
+    pub index: usize,
+  }
+
+  #[derive(Shrinkwrap)]
+  #[repr(transparent)]
+  #[shrinkwrap(transparent, delegate_constructors("new"), forward_borrow)]
+  pub struct Names(pub ::std::vec::Vec<::std::string::String>);
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(owned = "PathBuf")]
+  #[repr(transparent)]
+  pub struct Path(::std::path::Path);
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(borrowed = "Path")]
+  pub struct PathBuf(pub ::std::path::PathBuf);
+}
+
+use shadowed::IntoName;
+
+#[test]
+fn test_shadowed_names() {
+  let mut name = String::from("ada").into_name();
+  name.push_str(" lovelace");
+
+  assert_eq!(name.map_ref(|inner| inner.len()), 12);
+  assert_eq!(shadowed::Name::wrap_opt(Some("grace".into())).unwrap().take(), "grace");
+
+  let mut tagged = shadowed::Tagged { value: 1, tag: "count" };
+  *tagged += 1;
+
+  assert_eq!(*tagged, 2);
+
+  let id = shadowed::Id::new("x".into());
+  assert_eq!(id.with_index(4).index, 4);
+  assert!(shadowed::Names::new().is_empty());
+
+  let path = shadowed::PathBuf("/tmp".into());
+  assert_eq!(path.to_str(), Some("/tmp"));
+}
@@ -22,6 +22,45 @@ struct FileContents {
   linked_inodes: u64,
 }
 
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = "addr")]
+struct Mailbox {
+  owner: String,
+  addr: String,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = 2)]
+struct LineSpan(u64, u64, String);
+
+#[derive(Shrinkwrap)]
+struct Interned {
+  #[shrinkwrap(ignore)]
+  id: u32,
+  text: String,
+}
+
+struct Meters;
+
+#[derive(Shrinkwrap)]
+struct Length<Unit>(f64, std::marker::PhantomData<Unit>);
+
+struct Config {
+  addr: String,
+  port: u16,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = "config.addr", main_type = "String", accessors)]
+struct Server {
+  name: String,
+  config: Config,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = "0.port", main_type = "u16", mutable)]
+struct Listener(Config);
+
 #[test]
 fn test_tuple_can_deref() {
   let email = Email("chiya+snacks@natsumeya.jp".into());
@@ -53,5 +92,178 @@ fn test_multi_can_deref() {
     linked_inodes: 3,
   };
 
-  assert!(contents.len() > 0);
+  assert!(!contents.is_empty());
+}
+
+#[test]
+fn test_struct_level_main_field() {
+  let mailbox = Mailbox {
+    owner: "Chiya".into(),
+    addr: "chiya+snacks@natsumeya.jp".into(),
+  };
+  let span = LineSpan(0, 24, "  impl  ".into());
+
+  assert!(mailbox.contains('+'));
+  assert_eq!(span.trim(), "impl");
+}
+
+#[test]
+fn test_nested_main_field() {
+  let config = Config {
+    addr: "natsumeya.jp".into(),
+    port: 80,
+  };
+  let server = Server {
+    name: "www".into(),
+    config,
+  };
+
+  assert!(server.ends_with(".jp"));
+  assert_eq!(server.into_inner(), "natsumeya.jp");
+}
+
+#[test]
+fn test_nested_main_field_mut() {
+  let mut listener = Listener(Config {
+    addr: "natsumeya.jp".into(),
+    port: 80,
+  });
+
+  *listener += 8000;
+
+  assert_eq!(listener.0.port, 8080);
+}
+
+#[test]
+fn test_ignored_field() {
+  let interned = Interned {
+    id: 3,
+    text: "impl".into(),
+  };
+
+  assert_eq!(interned.len(), 4);
+}
+
+#[test]
+fn test_phantom_data_ignored() {
+  let length: Length<Meters> = Length(1.5, std::marker::PhantomData);
+
+  assert_eq!(*length, 1.5);
+}
+
+#[derive(Shrinkwrap)]
+#[cfg_attr(all(), shrinkwrap(accessors, rename(into_inner = "into_name")))]
+struct Conditional {
+  #[cfg_attr(all(), shrinkwrap(main_field))]
+  name: String,
+  #[cfg_attr(any(), shrinkwrap(main_field))]
+  id: u32,
+}
+
+#[test]
+fn test_cfg_attr_field_options() {
+  let conditional = Conditional {
+    name: "ada".into(),
+    id: 1,
+  };
+
+  assert_eq!(conditional.len(), 3);
+  assert_eq!(conditional.id, 1);
+  assert_eq!(conditional.into_name(), "ada");
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(accessors)]
+struct Gated {
+  #[shrinkwrap(main_field)]
+  name: String,
+  #[cfg(any())]
+  debug_info: String,
+}
+
+#[test]
+fn test_cfg_gated_field() {
+  let gated = Gated { name: "ada".into() };
+
+  assert_eq!(gated.len(), 3);
+  assert_eq!(gated.into_inner(), "ada");
+}
+
+trait Plugin {
+  fn name(&self) -> &str;
+}
+
+struct Echo(String);
+
+impl Plugin for Echo {
+  fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, deref_via_inner, as_ref_via_inner)]
+struct Loaded(Box<dyn Plugin>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(deref_via_inner, as_ref_via_inner)]
+struct Shared(std::sync::Arc<dyn Plugin>);
+
+#[test]
+fn test_as_ref_via_inner() {
+  fn name_of<P: AsRef<dyn Plugin>>(plugin: &P) -> &str {
+    plugin.as_ref().name()
+  }
+
+  let mut loaded = Loaded(Box::new(Echo("echo".into())));
+  let shared = Shared(std::sync::Arc::new(Echo("shared".into())));
+
+  assert_eq!(loaded.name(), "echo");
+  assert_eq!(name_of(&loaded), "echo");
+  assert_eq!(AsMut::<dyn Plugin>::as_mut(&mut loaded).name(), "echo");
+  assert_eq!(name_of(&shared), "shared");
+}
+
+// A borrowed inner value derefs to what it borrows, not to the reference.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(deref_via_inner, as_ref_via_inner)]
+struct Name<'a>(&'a str);
+
+#[test]
+fn test_reference_inner() {
+  fn len_of<S: AsRef<str>>(s: S) -> usize {
+    s.as_ref().len()
+  }
+
+  let name = Name("chiya");
+  let target: &str = &name;
+
+  assert_eq!(target, "chiya");
+  assert_eq!(name.to_uppercase(), "CHIYA");
+  assert_eq!(len_of(&name), 5);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(slice, mutable)]
+struct Block([u8; 4]);
+
+#[test]
+fn test_slice() {
+  fn checksum<B: AsRef<[u8]>>(bytes: B) -> u32 {
+    bytes.as_ref().iter().map(|&b| u32::from(b)).sum()
+  }
+
+  let mut block = Block([1, 2, 3, 4]);
+  let slice: &[u8] = &block;
+
+  assert_eq!(slice, &[1, 2, 3, 4]);
+  assert_eq!(block.len(), 4);
+  assert_eq!(checksum(&block), 10);
+  assert_eq!(block.as_ref() as &[u8; 4], &[1, 2, 3, 4]);
+
+  block.as_mut_slice()[0] = 9;
+  block[1] = 8;
+  AsMut::<[u8]>::as_mut(&mut block)[2] = 7;
+
+  assert_eq!(block.as_slice(), &[9, 8, 7, 4]);
 }
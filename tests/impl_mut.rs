@@ -5,9 +5,18 @@ extern crate shrinkwraprs;
 extern crate core;
 
 #[derive(Shrinkwrap)]
-#[shrinkwrap(mutable)]
+#[shrinkwrap(mutable, mem_helpers)]
 struct Email(String);
 
+// Without `mem_helpers`, `replace()` and friends are the inner type's.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable)]
+struct Buf(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable)]
+struct Ids(Vec<u32>);
+
 #[derive(Shrinkwrap)]
 #[shrinkwrap(mutable)]
 struct CodeSpan(u64, u64, #[shrinkwrap(main_field)] String);
@@ -26,6 +35,69 @@ struct FileContents {
   linked_inodes: u64,
 }
 
+#[derive(Shrinkwrap, ShrinkwrapMut)]
+struct InputBuffer {
+  buffer: String,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, accessors)]
+struct Draft {
+  #[shrinkwrap(main_field)]
+  published: String,
+  #[shrinkwrap(main_field_mut)]
+  pending: String,
+}
+
+// With the derives in separate attributes, and generics and a custom deref
+// target for the mutable impls to agree with.
+#[derive(Shrinkwrap)]
+#[derive(ShrinkwrapMut)]
+#[shrinkwrap(deref_target = "[T]", accessors)]
+struct Stack<T>(Vec<T>);
+
+// The field is private, but the struct opts out of the visibility check.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, unsafe_ignore_visibility)]
+pub struct Scratch(Vec<u8>);
+
+mod bank {
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(mut_access = "pub(crate)")]
+  pub struct Balance(u64);
+
+  impl Balance {
+    pub fn new(cents: u64) -> Self {
+      Balance(cents)
+    }
+  }
+}
+
+mod outer {
+  pub mod inner {
+    // The struct's visibility starts at the crate root and the field's at
+    // `self`, so shrinkwraprs can't compare them.
+    #[derive(Shrinkwrap)]
+    #[shrinkwrap(mutable, visibility_policy = "allow")]
+    pub(in outer) struct Counter(pub(super) u32);
+  }
+
+  #[test]
+  fn test_visibility_policy_allow() {
+    let mut counter = inner::Counter(1);
+
+    *counter += 1;
+
+    assert_eq!(*counter, 2);
+  }
+}
+
+struct NotDefault;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable)]
+struct Unique(NotDefault);
+
 #[test]
 fn test_tuple_can_deref_mut() {
   let mut email = Email("chiya+snacks@natsumeya.jp".into());
@@ -58,3 +130,93 @@ fn test_multi_can_deref_mut() {
 
   contents.push_str("fdjskl");
 }
+
+#[test]
+fn test_derive_mut_can_deref_mut() {
+  let mut input = InputBuffer {
+    buffer: "some".into(),
+  };
+
+  input.push_str(" values");
+}
+
+#[test]
+fn test_replace_take_swap() {
+  let mut email = Email("chiya+snacks@natsumeya.jp".into());
+  let mut other = Email("aoi.miyamori@musashino.jp".into());
+
+  email.swap(&mut other);
+  assert_eq!(*other, "chiya+snacks@natsumeya.jp");
+
+  let old = email.replace("satsuki@honnouji.edu".into());
+  assert_eq!(old, "aoi.miyamori@musashino.jp");
+
+  let taken = email.take();
+  assert_eq!(taken, "satsuki@honnouji.edu");
+  assert!(email.is_empty());
+}
+
+#[test]
+fn test_inner_methods_not_shadowed() {
+  let buf = Buf("a-a".into());
+  let mut ids = Ids(vec![1, 2]);
+
+  ids.swap(0, 1);
+
+  assert_eq!(buf.replace("a", "b"), "b-b");
+  assert_eq!(*ids, [2, 1]);
+}
+
+#[test]
+fn test_set() {
+  let mut email = Email("chiya+snacks@natsumeya.jp".into());
+
+  email.set("aoi.miyamori@musashino.jp".into());
+
+  assert_eq!(*email, "aoi.miyamori@musashino.jp");
+}
+
+#[test]
+fn test_separate_mut_field() {
+  let mut draft = Draft {
+    published: "v1".into(),
+    pending: String::new(),
+  };
+
+  draft.push_str("v2");
+  draft.as_inner_mut().push('!');
+
+  assert_eq!(*draft, "v1");
+  assert_eq!(draft.pending, "v2!");
+}
+
+#[test]
+fn test_derive_mut_generic_deref_target() {
+  use std::borrow::BorrowMut;
+
+  let mut stack = Stack(vec![1, 2]);
+
+  stack[0] = 3;
+  stack.as_inner_mut().push(4);
+  BorrowMut::<Vec<i32>>::borrow_mut(&mut stack).push(5);
+
+  assert_eq!(&*stack, &[3, 2, 4, 5]);
+}
+
+#[test]
+fn test_unsafe_ignore_visibility() {
+  let mut scratch = Scratch(vec![]);
+
+  scratch.push(1);
+
+  assert_eq!(*scratch, [1]);
+}
+
+#[test]
+fn test_mut_access() {
+  let mut balance = bank::Balance::new(100);
+
+  *balance.inner_mut() += 50;
+
+  assert_eq!(*balance, 150);
+}
@@ -0,0 +1,428 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(accessors)]
+struct Email(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(accessors)]
+struct CodeSpan(u64, u64, #[shrinkwrap(main_field)] String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(accessors)]
+struct PhoneNumber {
+  number: String,
+}
+
+// Without `accessors`, the struct is free to have its own.
+#[derive(Shrinkwrap)]
+struct Celsius(f64);
+
+impl Celsius {
+  fn into_inner(self) -> f64 {
+    self.0 + 273.15
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(accessors, rename(into_inner = "into_raw"))]
+struct FileContents {
+  #[shrinkwrap(main_field)]
+  contents: String,
+  linked_inodes: u64,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor)]
+pub struct Username(
+  /// Case-folded, so that lookups don't depend on how it was typed.
+  String,
+);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor_vis = "pub(crate)")]
+pub struct Token {
+  #[shrinkwrap(main_field)]
+  text: String,
+  line: u32,
+  column: u32,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor_into)]
+pub struct Address(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor, const_fns, accessors)]
+pub struct Width(u64);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor, const_fns, accessors)]
+pub struct Greeting(&'static str);
+
+// Moving `id` out would drop `label`, which a const `into_inner()` can't
+// do, so only `as_inner()` is const here.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(const_fns, accessors)]
+pub struct Labelled {
+  #[shrinkwrap(main_field)]
+  id: u32,
+  label: String,
+}
+
+const DEFAULT_WIDTH: Width = Width::new(80);
+const HALF_WIDTH: u64 = Width::new(40).into_inner();
+const HELLO: Greeting = Greeting::new("hello");
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, accessors)]
+struct InputBuffer(String);
+
+const HELLO_LEN: usize = HELLO.as_inner().len();
+
+mod lexer {
+  use std::marker::PhantomData;
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(constructor, with_fields)]
+  pub struct Lexeme {
+    #[shrinkwrap(main_field)]
+    text: String,
+    pub line: u32,
+    pub column: u32,
+    marker: PhantomData<()>,
+  }
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(constructor, with_fields)]
+  pub struct Comment {
+    #[shrinkwrap(main_field, borrow = false)]
+    text: String,
+    #[shrinkwrap(borrow, rename = "line")]
+    pub line_number: u32,
+  }
+
+  // `text` is private, so `into_parts()` is too, and there's no `From`.
+  pub fn split(lexeme: Lexeme) -> (String, u32, u32) {
+    let (text, line, column, _) = lexeme.into_parts();
+
+    (text, line, column)
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(copy_get)]
+struct UserId(u64);
+
+// `get()` just isn't callable when the inner type isn't `Copy`.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(copy_get)]
+struct Handle(String);
+
+#[test]
+fn test_into_inner() {
+  let email = Email("chiya+snacks@natsumeya.jp".into());
+  let span = CodeSpan(0, 4, "impl".into());
+  let number = PhoneNumber {
+    number: "+1 (800) 273-8255".into(),
+  };
+
+  assert_eq!(email.into_inner(), "chiya+snacks@natsumeya.jp");
+  assert_eq!(span.into_inner(), "impl");
+  assert_eq!(number.into_inner(), "+1 (800) 273-8255");
+}
+
+#[test]
+fn test_renamed_into_inner() {
+  let contents = FileContents {
+    contents: "fjkfdlsjfkdlsjflks".into(),
+    linked_inodes: 3,
+  };
+
+  assert_eq!(contents.into_raw(), "fjkfdlsjfkdlsjflks");
+}
+
+#[test]
+fn test_constructor() {
+  let username = Username::new("chiya".into());
+  let token = Token::new("impl".into());
+
+  assert_eq!(*username, "chiya");
+  assert_eq!(*token, "impl");
+  assert_eq!((token.line, token.column), (0, 0));
+}
+
+#[test]
+fn test_constructor_into() {
+  let from_str = Address::new("chiya+snacks@natsumeya.jp");
+  let from_string = Address::new(String::from("chiya+snacks@natsumeya.jp"));
+
+  assert_eq!(*from_str, *from_string);
+}
+
+#[test]
+fn test_const_fns() {
+  assert_eq!(*DEFAULT_WIDTH, 80);
+  assert_eq!(HALF_WIDTH, 40);
+  assert_eq!(HELLO.len(), 5);
+
+  let labelled = Labelled {
+    id: 7,
+    label: "seven".to_owned(),
+  };
+  assert_eq!(*labelled.as_inner(), 7);
+  assert_eq!(labelled.into_inner(), 7);
+}
+
+#[test]
+fn test_own_into_inner() {
+  use shrinkwraprs::Shrinkwrap;
+
+  assert_eq!(Celsius(0.0).into_inner(), 273.15);
+  assert_eq!(Shrinkwrap::into_inner(Celsius(0.0)), 0.0);
+}
+
+#[test]
+fn test_as_inner() {
+  let email = Email("chiya+snacks@natsumeya.jp".into());
+  let mut input = InputBuffer("some".into());
+
+  input.as_inner_mut().push_str(" values");
+
+  assert!(email.as_inner().contains('+'));
+  assert_eq!(input.as_inner(), "some values");
+  assert_eq!(HELLO_LEN, 5);
+}
+
+#[test]
+fn test_copy_get() {
+  let id = UserId(7);
+
+  assert_eq!(id.get(), 7);
+  assert!(id.get() > 5);
+  assert_eq!(Handle("chino".into()).len(), 5);
+}
+
+#[test]
+fn test_with_fields() {
+  let lexeme = lexer::Lexeme::new("impl".into()).with_line(3).with_column(4);
+
+  assert_eq!(*lexeme, "impl");
+  assert_eq!((lexeme.line, lexeme.column), (3, 4));
+}
+
+#[test]
+fn test_combined_field_options() {
+  use std::borrow::Borrow;
+
+  let comment = lexer::Comment::new("// hi".into()).with_line(7);
+  let line: &u32 = comment.borrow();
+
+  assert_eq!(*comment, "// hi");
+  assert_eq!(*line, 7);
+}
+
+#[test]
+fn test_into_parts() {
+  let span = CodeSpan(3, 7, "impl".into());
+  let contents = FileContents {
+    contents: "fn main() {}".into(),
+    linked_inodes: 2,
+  };
+
+  assert_eq!(span.into_parts(), (3, 7, "impl".into()));
+  assert_eq!(contents.into_parts(), ("fn main() {}".into(), 2));
+
+  let lexeme = lexer::Lexeme::new("impl".into()).with_line(3).with_column(1);
+  assert_eq!(lexer::split(lexeme), ("impl".into(), 3, 1));
+}
+
+#[derive(Shrinkwrap)]
+struct Tagged<T>(#[shrinkwrap(main_field)] T, &'static str);
+
+#[test]
+fn test_into_tuple() {
+  let (start, end, text) = CodeSpan(3, 7, "impl".into()).into();
+  let (value, tag): (u8, _) = Tagged(7, "seven").into();
+
+  assert_eq!((start, end, text), (3, 7, "impl".into()));
+  assert_eq!((value, tag), (7, "seven"));
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(constructor, must_use)]
+struct Checked(u64);
+
+#[test]
+fn test_must_use() {
+  let checked = Checked::new(7).map(|value| value + 1);
+
+  assert_eq!(checked, 8);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(
+  mutable,
+  constructor,
+  accessors,
+  rename(as_inner = "raw", as_inner_mut = "raw_mut", new = "wrap", map = "apply")
+)]
+struct RawBytes(Vec<u8>);
+
+#[test]
+fn test_rename_more() {
+  let mut bytes = RawBytes::wrap(vec![1, 2]);
+
+  bytes.raw_mut().push(3);
+
+  assert_eq!(bytes.raw(), &[1, 2, 3]);
+  assert_eq!(bytes.apply(|bytes| bytes.len()), 3);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(option)]
+struct Nickname(Option<String>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(option)]
+struct Retries {
+  #[shrinkwrap(main_field)]
+  limit: Option<u32>,
+  name: &'static str,
+}
+
+#[test]
+fn test_option() {
+  let nickname = Nickname(Some("jo".to_owned()));
+  let anonymous = Nickname(None);
+
+  assert!(nickname.is_some());
+  assert!(anonymous.is_none());
+  assert_eq!(nickname.as_opt(), Some(&"jo".to_owned()));
+  assert_eq!(nickname.as_deref(), Some("jo"));
+  assert_eq!(anonymous.as_deref(), None);
+  assert_eq!(nickname.unwrap_or_default(), "jo");
+  assert_eq!(anonymous.unwrap_or_else(|| "anonymous".to_owned()), "anonymous");
+
+  let retries = Retries { limit: None, name: "fetch" };
+
+  assert_eq!(retries.as_opt(), None);
+  assert_eq!(retries.unwrap_or(3), 3);
+}
+
+mod cache {
+  use std::collections::HashMap;
+  use std::sync::{Mutex, RwLock};
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(lock)]
+  pub struct Counter(pub Mutex<u32>);
+
+  #[derive(Shrinkwrap)]
+  #[shrinkwrap(lock)]
+  pub struct Cache(pub RwLock<HashMap<String, u32>>);
+}
+
+#[test]
+fn test_lock() {
+  use cache::{Cache, Counter};
+
+  let counter = Counter(Default::default());
+  let cache = Cache(Default::default());
+
+  *counter.lock().unwrap() += 1;
+  cache.write().unwrap().insert("answer".to_owned(), 42);
+
+  assert_eq!(*counter.lock().unwrap(), 1);
+  assert_eq!(cache.read().unwrap().get("answer"), Some(&42));
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cell)]
+struct Log(std::cell::RefCell<Vec<String>>);
+
+#[test]
+fn test_ref_cell() {
+  let log = Log(Default::default());
+
+  log.borrow_mut().push("started".to_owned());
+
+  let entries = log.borrow();
+
+  assert_eq!(entries.len(), 1);
+  assert!(log.try_borrow().is_ok());
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cell, rename(borrow = "read", try_borrow = "try_read", borrow_mut = "write"))]
+struct Journal(std::cell::RefCell<Vec<String>>);
+
+#[test]
+fn test_renamed_ref_cell() {
+  use std::borrow::Borrow;
+
+  let journal = Journal(Default::default());
+
+  journal.write().push("started".to_owned());
+
+  let cell: &std::cell::RefCell<Vec<String>> = journal.borrow();
+  assert_eq!(journal.read().len(), 1);
+  assert!(journal.try_read().is_ok());
+  assert_eq!(cell.borrow().len(), 1);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cell)]
+struct Hits(std::cell::Cell<u32>);
+
+#[test]
+fn test_cell() {
+  let hits = Hits(Default::default());
+
+  hits.set(2);
+
+  assert_eq!(hits.update(|hits| hits + 1), 3);
+  assert_eq!(hits.replace(10), 3);
+  assert_eq!(hits.get(), 10);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cell, rename(get = "count", set = "reset", update = "bump"))]
+struct Misses(std::cell::Cell<u32>);
+
+#[test]
+fn test_renamed_cell() {
+  let misses = Misses(Default::default());
+
+  misses.reset(4);
+
+  assert_eq!(misses.bump(|misses| misses * 2), 8);
+  assert_eq!(misses.count(), 8);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(atomic)]
+struct Requests(std::sync::atomic::AtomicU64);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(atomic)]
+struct ShuttingDown(std::sync::atomic::AtomicBool);
+
+#[test]
+fn test_atomic() {
+  use std::sync::atomic::Ordering;
+
+  let requests = Requests(Default::default());
+  let shutting_down = ShuttingDown(Default::default());
+
+  requests.store(5, Ordering::Relaxed);
+  shutting_down.store(true, Ordering::Relaxed);
+
+  assert_eq!(requests.fetch_add(2, Ordering::Relaxed), 5);
+  assert_eq!(requests.fetch_sub(1, Ordering::Relaxed), 7);
+  assert_eq!(requests.load(Ordering::Relaxed), 6);
+  assert!(shutting_down.load(Ordering::Relaxed));
+}
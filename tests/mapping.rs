@@ -7,6 +7,22 @@ extern crate core;
 #[derive(Shrinkwrap)]
 pub struct Email(String);
 
+#[derive(Shrinkwrap)]
+pub struct Token {
+  #[shrinkwrap(main_field)]
+  text: String,
+  line: u32,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(rename(
+  map = "map_into",
+  map_ref = "map_ref_into",
+  transform = "map",
+  transform_ref = "map_ref"
+))]
+pub struct Nickname(String);
+
 #[test]
 fn test_map_mut() {
   let mut email = Email("aoi.miyamori@musashino.jp".into());
@@ -17,3 +33,38 @@ fn test_map_mut() {
 
   assert_eq!(len2, len1 + 3);
 }
+
+#[test]
+fn test_transform() {
+  let email = Email("aoi.miyamori@musashino.jp".into());
+  let email = email.transform(|s| s.to_uppercase());
+
+  let token = Token {
+    text: "impl".into(),
+    line: 3,
+  };
+  let token = token.transform(|s| s + "l");
+
+  assert_eq!(*email, "AOI.MIYAMORI@MUSASHINO.JP");
+  assert_eq!((token.text.as_str(), token.line), ("impll", 3));
+}
+
+#[test]
+fn test_transform_ref() {
+  let email = Email("aoi.miyamori@musashino.jp".into());
+  let domain = email.transform_ref(|s| s.split('@').nth(1).unwrap().into());
+
+  assert_eq!(*domain, "musashino.jp");
+  assert_eq!(*email, "aoi.miyamori@musashino.jp");
+}
+
+#[test]
+fn test_transform_renamed_to_map() {
+  let nickname = Nickname("chino".into());
+  let shouted = nickname.map_ref(|s| s.to_uppercase());
+  let len = nickname.map_into(|s| s.len());
+
+  assert_eq!(*shouted, "CHINO");
+  assert_eq!(len, 5);
+  assert_eq!(*shouted.map(|s| s + "!"), "CHINO!");
+}
@@ -0,0 +1,93 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+shrinkwrap_newtypes! {
+  #![shrinkwrap(constructor, copy_get)]
+
+  /// How wide something is.
+  pub struct Width(u64);
+
+  #[derive(Clone, Copy, Debug, PartialEq)]
+  pub struct Height(u64);
+
+  #[shrinkwrap(mutable)]
+  struct Depth {
+    #[shrinkwrap(main_field)]
+    value: u64,
+    unit: &'static str,
+  }
+}
+
+shrinkwrap_newtypes! {
+  struct Label(String);
+}
+
+shrinkwrap_newtypes! {
+  #![shrinkwrap(mutable)]
+
+  struct Score(u32);
+
+  // Opted out of the shared `mutable`, so it's free to implement `DerefMut`
+  // on its own terms.
+  #[shrinkwrap(main_field_mut = "none")]
+  struct Total(u32);
+}
+
+impl std::ops::DerefMut for Total {
+  fn deref_mut(&mut self) -> &mut u32 {
+    &mut self.0
+  }
+}
+
+newtype! {
+  /// A port that doesn't need root to bind to.
+  pub struct Port(u16) where |port| (1024..=65535).contains(port);
+
+  #[shrinkwrap(copy_get)]
+  struct Count(u32) where |n| *n > 0;
+}
+
+#[test]
+fn test_shared_options() {
+  let width = Width::new(3);
+  let height = Height::new(4);
+
+  assert_eq!(width.get() * height.get(), 12);
+  assert_eq!(height, Height(4));
+}
+
+#[test]
+fn test_own_options() {
+  let mut depth = Depth::new(5);
+  *depth += 1;
+
+  assert_eq!(*depth, 6);
+  assert_eq!(depth.unit, "");
+}
+
+#[test]
+fn test_opt_out_of_mutable() {
+  let mut score = Score(1);
+  let mut total = Total(2);
+  *score += 1;
+  *total += 1;
+
+  assert_eq!((*score, *total), (2, 3));
+}
+
+#[test]
+fn test_no_shared_options() {
+  assert_eq!(Label("box".into()).len(), 3);
+}
+
+#[test]
+fn test_newtype_checks() {
+  use std::convert::TryFrom;
+
+  assert_eq!(*Port::try_new(8080).unwrap(), 8080);
+  assert_eq!(Port::try_new(80).err(), Some(80));
+  assert_eq!(Count::try_from(4).map(|n| n.get()), Ok(4));
+  assert_eq!(Count::try_from(0).err(), Some(0));
+}
@@ -0,0 +1,77 @@
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::borrow::{Borrow, Cow};
+use std::collections::HashSet;
+use std::marker::PhantomData;
+
+#[derive(Debug, PartialEq, Eq, Hash, Shrinkwrap)]
+#[shrinkwrap(owned = "EmailBuf")]
+#[repr(transparent)]
+struct Email(str);
+
+#[derive(Debug, PartialEq, Eq, Hash, Shrinkwrap)]
+#[shrinkwrap(borrowed = "Email")]
+struct EmailBuf(String);
+
+// `A` doesn't show up in `List<T>`, so `to_owned()` builds a `ListBuf<T>`.
+#[derive(Debug, PartialEq, Shrinkwrap)]
+#[shrinkwrap(owned = "ListBuf<T>")]
+#[repr(transparent)]
+struct List<T>([T]);
+
+#[derive(Debug, PartialEq, Shrinkwrap)]
+#[shrinkwrap(borrowed = "List<T>")]
+struct ListBuf<T, A = ()>(#[shrinkwrap(main_field)] Vec<T>, PhantomData<A>)
+where
+  T: Clone;
+
+impl Email {
+  fn domain(&self) -> &str {
+    self.split('@').nth(1).unwrap_or("")
+  }
+}
+
+#[test]
+fn test_owned_derefs_to_borrowed() {
+  let email = EmailBuf("ada@example.com".into());
+
+  assert_eq!(email.domain(), "example.com");
+  assert_eq!(email.len(), 15);
+}
+
+#[test]
+fn test_to_owned_round_trip() {
+  let email = EmailBuf("ada@example.com".into());
+  let borrowed: &Email = email.borrow();
+  let owned: EmailBuf = borrowed.to_owned();
+
+  assert_eq!(owned, email);
+  assert_eq!(&owned.0, "ada@example.com");
+}
+
+#[test]
+fn test_generic_to_owned() {
+  let list: ListBuf<u32> = ListBuf(vec![1, 2, 3], PhantomData);
+  let borrowed: &List<u32> = list.borrow();
+  let owned = borrowed.to_owned();
+
+  assert_eq!(owned, list);
+  assert_eq!(owned.len(), 3);
+}
+
+#[test]
+fn test_cow() {
+  let email = Email::from_ref("ada@example.com");
+  let cow: Cow<Email> = Cow::Borrowed(email);
+
+  assert_eq!(cow.into_owned().0, "ada@example.com");
+}
+
+#[test]
+fn test_lookup_by_borrowed() {
+  let mut emails = HashSet::new();
+  emails.insert(EmailBuf("ada@example.com".into()));
+
+  assert!(emails.contains(Email::from_ref("ada@example.com")));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "parking_lot")]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use shrinkwraprs::__private::parking_lot::{Mutex, RwLock};
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(lock = "parking_lot")]
+struct Inbox(Mutex<Vec<String>>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(lock = "parking_lot")]
+struct Settings(RwLock<(String, u16)>);
+
+// The backend is per struct, so `std`'s locks keep working alongside.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(lock)]
+struct Counter(std::sync::Mutex<u32>);
+
+#[test]
+fn test_parking_lot_guards() {
+  let inbox = Inbox(Default::default());
+  let settings = Settings(RwLock::new(("localhost".to_owned(), 80)));
+  let counter = Counter(Default::default());
+
+  inbox.lock().push("hi".to_owned());
+  settings.write().1 = 8080;
+  *counter.lock().unwrap() += 1;
+
+  assert_eq!(inbox.lock().len(), 1);
+  assert_eq!(settings.read().1, 8080);
+  assert_eq!(*counter.lock().unwrap(), 1);
+}
+
+#[test]
+fn test_parking_lot_mapped_guards() {
+  let inbox = Inbox(Default::default());
+  let settings = Settings(RwLock::new(("localhost".to_owned(), 80)));
+
+  inbox.lock_mapped(|messages| messages).push("hi".to_owned());
+  *settings.write_mapped(|settings| &mut settings.1) = 443;
+
+  assert_eq!(&*settings.read_mapped(|settings| settings.0.as_str()), "localhost");
+  assert_eq!(*settings.read_mapped(|settings| &settings.1), 443);
+  assert_eq!(inbox.lock().len(), 1);
+}
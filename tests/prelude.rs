@@ -0,0 +1,16 @@
+#![allow(unused_variables, dead_code)]
+
+extern crate shrinkwraprs;
+
+use shrinkwraprs::prelude::*;
+
+#[derive(Shrinkwrap, ShrinkwrapMut)]
+struct InputBuffer(String);
+
+#[test]
+fn test_prelude_imports() {
+  let mut input = InputBuffer("some".into());
+
+  input.as_inner_mut().push_str(" values");
+  assert_eq!(input.as_inner(), "some values");
+}
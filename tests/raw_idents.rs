@@ -0,0 +1,65 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, constructor, with_fields, cow, accessors)]
+struct Token {
+  #[shrinkwrap(main_field)]
+  r#type: String,
+  r#match: u32,
+  #[shrinkwrap(borrow)]
+  r#loop: usize,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = "r#in")]
+struct Keyword {
+  r#in: u8,
+  line: u32,
+}
+
+struct Scope {
+  r#fn: u8,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(main_field = "r#struct.r#fn", main_type = "u8")]
+struct Nested {
+  r#struct: Scope,
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(ext_trait, accessors)]
+struct r#Match(String);
+
+#[test]
+fn test_raw_field_names() {
+  use std::borrow::Borrow;
+
+  let mut token = Token::new("ident".into()).with_match(3).with_loop(4);
+  token.push('s');
+
+  assert_eq!(token.r#match, 3);
+  assert_eq!(*Borrow::<usize>::borrow(&token), 4);
+  assert_eq!(token.into_inner(), "idents");
+}
+
+#[test]
+fn test_raw_named_main_field() {
+  let keyword = Keyword { r#in: 7, line: 1 };
+  let nested = Nested {
+    r#struct: Scope { r#fn: 9 },
+  };
+
+  assert_eq!(*keyword, 7);
+  assert_eq!(*nested, 9);
+}
+
+#[test]
+fn test_raw_struct_name() {
+  let matched = String::from("x").into_match();
+
+  assert_eq!(matched.as_inner(), "x");
+}
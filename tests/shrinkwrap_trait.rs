@@ -0,0 +1,78 @@
+#![allow(unused_variables, dead_code)]
+
+extern crate shrinkwraprs;
+
+use shrinkwraprs::{Shrinkwrap, ShrinkwrapMut};
+
+#[derive(Shrinkwrap)]
+struct Email(String);
+
+#[derive(Shrinkwrap)]
+struct CodeSpan(u64, u64, #[shrinkwrap(main_field)] String);
+
+#[derive(Shrinkwrap)]
+struct GenericType<T>(T);
+
+#[derive(Shrinkwrap, ShrinkwrapMut)]
+struct InputBuffer(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable)]
+struct OutputBuffer(String);
+
+fn inner_len<T>(wrapped: &T) -> usize
+where
+  T: Shrinkwrap<Inner = String>,
+{
+  wrapped.as_inner().len()
+}
+
+fn strip_all<T: Shrinkwrap>(wrapped: Vec<T>) -> Vec<T::Inner> {
+  wrapped.into_iter().map(Shrinkwrap::into_inner).collect()
+}
+
+fn clear_all<T>(wrapped: &mut [T])
+where
+  T: ShrinkwrapMut<Inner = String>,
+{
+  for wrapped in wrapped {
+    wrapped.as_inner_mut().clear();
+  }
+}
+
+#[test]
+fn test_generic_over_wrappers() {
+  let email = Email("chiya+snacks@natsumeya.jp".into());
+  let span = CodeSpan(0, 24, "impl".into());
+
+  assert_eq!(inner_len(&email), 25);
+  assert_eq!(inner_len(&span), 4);
+}
+
+#[test]
+fn test_into_inner() {
+  let generics = vec![GenericType(1), GenericType(2)];
+
+  assert_eq!(strip_all(generics), vec![1, 2]);
+}
+
+#[test]
+fn test_generic_over_mut_wrappers() {
+  let mut inputs = vec![InputBuffer("a".into()), InputBuffer("b".into())];
+  let mut outputs = vec![OutputBuffer("c".into())];
+
+  clear_all(&mut inputs);
+  clear_all(&mut outputs);
+
+  assert!(inputs.iter().all(|input| input.is_empty()));
+  assert!(outputs[0].is_empty());
+}
+
+#[test]
+fn test_free_functions() {
+  let mut input = InputBuffer("some".into());
+
+  shrinkwraprs::peel_mut(&mut input).push_str(" values");
+  assert_eq!(shrinkwraprs::peel(&input).len(), 11);
+  assert_eq!(shrinkwraprs::strip(input), "some values");
+}
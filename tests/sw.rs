@@ -0,0 +1,40 @@
+#![allow(unused_variables, dead_code)]
+
+extern crate shrinkwraprs;
+
+use std::collections::HashSet;
+
+use shrinkwraprs::{Shrinkwrap, Sw};
+
+enum UserIdTag {}
+enum PostIdTag {}
+
+type UserId = Sw<UserIdTag, u64>;
+type PostId = Sw<PostIdTag, u64>;
+
+fn post_author(post: PostId) -> UserId {
+  UserId::new(*post + 1)
+}
+
+#[test]
+fn test_tagged_values() {
+  let author = post_author(PostId::new(41));
+
+  assert_eq!(author, UserId::new(42));
+  assert!(author > UserId::default());
+  assert_eq!(format!("{:?}", author), "Sw(42)");
+}
+
+#[test]
+fn test_tagged_traits() {
+  let mut ids: HashSet<UserId> = HashSet::new();
+  let id: UserId = 3.into();
+
+  ids.insert(id);
+  ids.insert(id);
+  assert_eq!(ids.len(), 1);
+
+  let mut id = id;
+  *id += 1;
+  assert_eq!(id.into_inner(), 4);
+}
@@ -0,0 +1,374 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::borrow::Borrow;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(traits(AsRef, Borrow))]
+struct Email(String);
+
+// Without `Deref`, `Email` can have a `len()` that means something else.
+impl Email {
+  fn len(&self) -> usize {
+    1
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, traits(Deref, AsRef, AsMut))]
+struct InputBuffer(String);
+
+#[test]
+fn test_listed_traits() {
+  let email = Email("chiya+snacks@natsumeya.jp".into());
+  let borrowed: &String = email.borrow();
+
+  assert_eq!(email.len(), 1);
+  assert_eq!(email.as_ref().len(), 25);
+  assert_eq!(borrowed.len(), 25);
+}
+
+#[test]
+fn test_listed_mut_traits() {
+  let mut input = InputBuffer("some".into());
+
+  input.as_mut().push_str(" values");
+
+  assert_eq!(input.len(), 11);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, no_deref)]
+struct PhoneNumber(String);
+
+impl PhoneNumber {
+  fn len(&self) -> usize {
+    1
+  }
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, no_borrow, no_as_mut)]
+struct Comment(String);
+
+#[test]
+fn test_opt_out() {
+  let mut number = PhoneNumber("+81 3-1234-5678".into());
+  let mut comment = Comment("nice".into());
+
+  number.as_mut().push('9');
+  comment.push('!');
+
+  assert_eq!(number.len(), 1);
+  assert_eq!(number.as_ref().len(), 16);
+  assert_eq!(*comment, "nice!");
+}
+
+// Two revisions with the same text are still different revisions, so this
+// can't promise to compare like its text.
+#[derive(Shrinkwrap)]
+#[derive(PartialEq, Eq, Hash)]
+#[shrinkwrap(no_borrow)]
+struct Revision {
+  #[shrinkwrap(main_field)]
+  text: String,
+  number: u32,
+}
+
+#[test]
+fn test_no_borrow_keeps_deref_and_as_ref() {
+  let first = Revision {
+    text: "draft".into(),
+    number: 1,
+  };
+  let second = Revision {
+    text: "draft".into(),
+    number: 2,
+  };
+
+  assert!(first != second);
+  assert_eq!(*first, *second);
+  assert_eq!(first.as_ref(), "draft");
+}
+
+#[derive(Shrinkwrap, ShrinkwrapMut)]
+#[shrinkwrap(no_deref_mut)]
+struct Username(String);
+
+#[test]
+fn test_shrinkwrap_mut_opt_out() {
+  let mut username = Username("chiya".into());
+
+  username.as_mut().push('!');
+
+  assert_eq!(*username, "chiya!");
+}
+
+#[derive(Shrinkwrap)]
+struct Token {
+  #[shrinkwrap(main_field)]
+  text: String,
+  #[shrinkwrap(borrow)]
+  line: u32,
+  column: u32,
+}
+
+#[test]
+fn test_secondary_borrows() {
+  let token = Token {
+    text: "impl".into(),
+    line: 3,
+    column: 4,
+  };
+  let line: &u32 = token.borrow();
+  let text: &String = token.borrow();
+
+  assert_eq!(*line, 3);
+  assert_eq!(AsRef::<u32>::as_ref(&token), &3);
+  assert_eq!(text, "impl");
+  assert_eq!(token.len(), 4);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(as_ref_all)]
+struct Pixel {
+  #[shrinkwrap(main_field)]
+  name: String,
+  position: (u32, u32),
+  brightness: f32,
+  saturation: f32,
+}
+
+#[test]
+fn test_as_ref_all() {
+  let pixel = Pixel {
+    name: "corner".into(),
+    position: (0, 0),
+    brightness: 0.5,
+    saturation: 0.25,
+  };
+  let position: &(u32, u32) = pixel.as_ref();
+  let name: &String = pixel.as_ref();
+
+  assert_eq!(position, &(0, 0));
+  assert_eq!(name, "corner");
+  assert_eq!(pixel.brightness + pixel.saturation, 0.75);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, deref_target = "str")]
+struct Title(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(deref_target = "[T]")]
+struct Stack<T>(Vec<T>);
+
+#[test]
+fn test_deref_target() {
+  let mut title = Title("dune".into());
+  title.make_ascii_uppercase();
+  let text: &str = &title;
+  let inner: &String = title.as_ref();
+
+  assert_eq!(text, "DUNE");
+  assert_eq!(inner, "DUNE");
+
+  let stack = Stack(vec![1, 2, 3]);
+  let items: &[i32] = &stack;
+
+  assert_eq!(items, &[1, 2, 3]);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, deref_via_inner)]
+struct Node<T>(Box<T>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(deref_via_inner)]
+struct Shared(std::sync::Arc<String>);
+
+#[test]
+fn test_deref_via_inner() {
+  let mut node = Node(Box::new(vec![1, 2]));
+  node.push(3);
+  let items: &Vec<i32> = &node;
+
+  assert_eq!(items, &vec![1, 2, 3]);
+
+  let shared = Shared(std::sync::Arc::new("shared".into()));
+  let text: &String = &shared;
+
+  assert_eq!(text, "shared");
+  assert_eq!(shared.len(), 6);
+}
+
+#[derive(Shrinkwrap)]
+struct Meters(f64);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(transitive)]
+struct Altitude(Meters);
+
+#[test]
+fn test_transitive() {
+  let altitude = Altitude(Meters(8848.0));
+  let meters: &Meters = altitude.as_ref();
+  let raw: &f64 = altitude.as_ref();
+  let borrowed: &f64 = altitude.borrow();
+
+  assert_eq!(meters.0, 8848.0);
+  assert_eq!(*raw, 8848.0);
+  assert_eq!(*borrowed, 8848.0);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(path_like)]
+struct ConfigPath(std::path::PathBuf);
+
+#[test]
+fn test_path_like() {
+  let config = ConfigPath("/etc/hosts".into());
+  let path: &std::path::Path = config.as_ref();
+  let os_str: &std::ffi::OsStr = config.as_ref();
+
+  assert_eq!(path.file_name(), Some(std::ffi::OsStr::new("hosts")));
+  assert_eq!(os_str, "/etc/hosts");
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, bytes)]
+struct Payload(Vec<u8>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, bytes)]
+struct Message(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(bytes)]
+struct Digest([u8; 4]);
+
+fn checksum<B: AsRef<[u8]>>(bytes: B) -> u32 {
+  bytes.as_ref().iter().map(|&byte| u32::from(byte)).sum()
+}
+
+#[test]
+fn test_bytes() {
+  let mut payload = Payload(vec![1, 2, 3]);
+  AsMut::<[u8]>::as_mut(&mut payload)[0] = 10;
+
+  assert_eq!(checksum(&payload), 15);
+  assert_eq!(checksum(Message("ab".into())), 195);
+  assert_eq!(checksum(Digest([0, 1, 2, 3])), 6);
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(forward_as_ref)]
+struct Hostname(String);
+
+#[test]
+fn test_forward_as_ref() {
+  let host = Hostname("localhost".into());
+  let text: &str = host.as_ref();
+  let bytes: &[u8] = host.as_ref();
+  let path: &std::path::Path = host.as_ref();
+
+  assert_eq!(text, "localhost");
+  assert_eq!(bytes, b"localhost");
+  assert_eq!(path, std::path::Path::new("localhost"));
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(forward_borrow)]
+struct Key(String);
+
+#[test]
+fn test_forward_borrow() {
+  use std::collections::{BTreeSet, HashMap};
+
+  let mut ages = HashMap::new();
+  ages.insert(Key("ada".into()), 36);
+  let keys: BTreeSet<_> = vec![Key("b".into()), Key("a".into())].into_iter().collect();
+
+  assert_eq!(ages.get("ada"), Some(&36));
+  assert!(keys.contains("a"));
+  assert!(Key("a".into()) < Key("b".into()));
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(forward_borrow)]
+struct Tags<T>(Vec<T>);
+
+#[test]
+fn test_forward_borrow_generic() {
+  use std::collections::HashSet;
+
+  let mut seen = HashSet::new();
+  seen.insert(Tags(vec!["a", "b"]));
+
+  assert!(seen.contains(&["a", "b"][..]));
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(cfg(any(), traits(Borrow)), cfg(all(), traits(AsRef)))]
+struct Gated(String);
+
+// This would conflict with the derived impl if it weren't gated out.
+impl Borrow<String> for Gated {
+  fn borrow(&self) -> &String {
+    &self.0
+  }
+}
+
+#[test]
+fn test_cfg_gated_traits() {
+  let gated = Gated("ada".into());
+  let text: &String = gated.as_ref();
+  let borrowed: &String = gated.borrow();
+
+  assert_eq!(text, "ada");
+  assert_eq!(borrowed, "ada");
+}
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mutable, inline(always))]
+struct Hot(Vec<u8>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(no_inline)]
+struct Cold(Vec<u8>);
+
+#[test]
+fn test_inline_options() {
+  let mut hot = Hot(vec![1]);
+  hot.push(2);
+  let cold = Cold(vec![3]);
+
+  assert_eq!(&**hot, &[1, 2]);
+  assert_eq!(AsRef::<Vec<u8>>::as_ref(&cold), &[3]);
+}
+
+// A wrapper around something that isn't `Send` isn't expected to be `Send`
+// either, so these only fail if another field takes the auto traits away.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(assert_send_sync)]
+struct Handle(std::rc::Rc<u32>);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(assert_send_sync)]
+struct Batch<T> {
+  #[shrinkwrap(main_field)]
+  items: Vec<T>,
+  label: &'static str,
+}
+
+#[test]
+fn test_assert_send_sync() {
+  fn is_send_sync<T: Send + Sync>(_: &T) {}
+
+  is_send_sync(&Batch {
+    items: vec![1, 2, 3],
+    label: "primes",
+  });
+}
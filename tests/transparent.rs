@@ -0,0 +1,158 @@
+#![allow(unused_variables, dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+extern crate core;
+
+use core::marker::PhantomData;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(transparent)]
+#[repr(transparent)]
+struct Email(String);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(transparent)]
+#[repr(transparent)]
+struct Length<Unit> {
+  #[shrinkwrap(main_field)]
+  value: f64,
+  unit: PhantomData<Unit>,
+}
+
+struct Meters;
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(transparent, assert_zero_cost)]
+#[repr(transparent)]
+struct Port(u16);
+
+// Not transparent, but the unit field doesn't take up any room.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(assert_zero_cost)]
+struct Celsius {
+  #[shrinkwrap(main_field)]
+  degrees: f32,
+  unit: PhantomData<()>,
+}
+
+#[test]
+fn test_from_ref() {
+  let raw = String::from("chiya+snacks@natsumeya.jp");
+  let email = Email::from_ref(&raw);
+
+  assert!(email.contains("+"));
+}
+
+#[test]
+fn test_from_mut() {
+  let mut raw = String::from("chiya+snacks@natsumeya.jp");
+  Email::from_mut(&mut raw).0.push_str(".co");
+
+  assert!(raw.ends_with(".co"));
+}
+
+#[test]
+fn test_from_ref_with_phantom() {
+  let raw = 3.5;
+  let length: &Length<Meters> = Length::from_ref(&raw);
+
+  assert_eq!(**length, 3.5);
+}
+
+#[test]
+fn test_slice_round_trip() {
+  let mut raw = vec![String::from("a@b.c"), String::from("d@e.f")];
+
+  assert_eq!(Email::from_slice(&raw)[1].as_str(), "d@e.f");
+
+  for email in Email::from_slice_mut(&mut raw) {
+    email.0.push_str(".co");
+  }
+
+  let emails = Email::from_slice(&raw);
+  let inner = Email::as_inner_slice(emails);
+
+  assert_eq!(inner, &["a@b.c.co", "d@e.f.co"]);
+}
+
+#[test]
+fn test_as_inner_slice_mut() {
+  let mut emails = vec![Email("a@b.c".into())];
+
+  Email::as_inner_slice_mut(&mut emails)[0].clear();
+
+  assert!(emails[0].is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_vec_round_trip() {
+  let raw = vec![String::from("a@b.c"), String::from("d@e.f")];
+  let ptr = raw.as_ptr() as usize;
+
+  let emails = Email::wrap_vec(raw);
+  assert!(emails[1].contains("@e"));
+
+  let raw = Email::unwrap_vec(emails);
+  assert_eq!(raw.as_ptr() as usize, ptr);
+  assert_eq!(raw, vec!["a@b.c", "d@e.f"]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_smart_pointer_round_trip() {
+  use std::rc::Rc;
+  use std::sync::Arc;
+
+  let boxed = Email::from_boxed(Box::new(String::from("a@b.c")));
+  assert_eq!(*Email::unwrap_boxed(boxed), "a@b.c");
+
+  let rc = Rc::new(String::from("a@b.c"));
+  let email = Email::from_rc(rc.clone());
+  assert_eq!(Rc::strong_count(&rc), 2);
+  assert!(Rc::ptr_eq(&Email::unwrap_rc(email), &rc));
+
+  let arc = Arc::new(String::from("a@b.c"));
+  let email = Email::from_arc(arc.clone());
+  assert!(email.contains("@b"));
+  assert!(Arc::ptr_eq(&Email::unwrap_arc(email), &arc));
+}
+
+#[test]
+fn test_array_round_trip() {
+  let lengths: [Length<Meters>; 3] = Length::wrap_array([1.0, 2.0, 3.0]);
+  assert_eq!(*lengths[2], 3.0);
+
+  let raw = Length::unwrap_array(lengths);
+  assert_eq!(raw, [1.0, 2.0, 3.0]);
+}
+
+#[test]
+fn test_array_ref_casts() {
+  let mut raw = [String::from("a@b.c"), String::from("d@e.f")];
+
+  Email::from_array_mut(&mut raw)[0].0.push_str(".co");
+
+  let emails = Email::from_array_ref(&raw);
+  assert_eq!(Email::as_inner_array(emails)[0], "a@b.c.co");
+}
+
+#[test]
+fn test_as_ptr() {
+  let mut email = Email("a@b.c".into());
+
+  assert_eq!(email.as_ptr(), &email.0 as *const String);
+  assert_eq!(email.as_mut_ptr() as *const Email, &email as *const Email);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_raw_round_trip() {
+  let ptr = Email::into_raw(Box::new(Email("a@b.c".into())));
+  let inner = unsafe { &*ptr };
+  assert_eq!(inner, "a@b.c");
+
+  let email = unsafe { Email::from_raw(ptr) };
+  assert!(email.contains("@b"));
+}
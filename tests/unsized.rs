@@ -0,0 +1,58 @@
+#![allow(dead_code)]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use std::borrow::Borrow;
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Shrinkwrap)]
+#[repr(transparent)]
+struct Label(str);
+
+#[derive(Shrinkwrap)]
+#[shrinkwrap(mut_access = "pub(crate)")]
+#[repr(transparent)]
+struct Bytes([u8]);
+
+#[derive(Shrinkwrap)]
+#[repr(transparent)]
+struct Samples<T>([T]);
+
+// `Path` isn't unsized as far as the derive can tell from its name.
+#[derive(Shrinkwrap)]
+#[shrinkwrap(unsized_inner)]
+#[repr(transparent)]
+struct Dir(Path);
+
+#[test]
+fn test_from_ref() {
+  let label = Label::from_ref("draft");
+
+  assert_eq!(label.len(), 5);
+  assert_eq!(Borrow::<str>::borrow(label), "draft");
+  assert_eq!(AsRef::<str>::as_ref(label), "draft");
+}
+
+#[test]
+fn test_from_mut() {
+  let mut raw = *b"abc";
+  let bytes = Bytes::from_mut(&mut raw);
+  bytes.inner_mut()[0] = b'x';
+
+  assert_eq!(&**bytes, b"xbc");
+}
+
+#[test]
+fn test_generic_slice() {
+  let samples = Samples::from_ref(&[1, 2, 3][..]);
+
+  assert_eq!(samples.iter().sum::<i32>(), 6);
+}
+
+#[test]
+fn test_unsized_inner() {
+  let dir = Dir::from_ref(Path::new("/tmp"));
+
+  assert!(dir.is_absolute());
+}
@@ -0,0 +1,20 @@
+#![cfg(feature = "uuid")]
+
+#[macro_use]
+extern crate shrinkwraprs;
+
+use shrinkwraprs::uuid::Uuid;
+
+#[derive(Shrinkwrap, Debug, PartialEq)]
+#[shrinkwrap(uuid)]
+struct OrderId(Uuid);
+
+#[test]
+fn test_uuid_constructors() {
+  let id = OrderId::parse_str("67e55044-10b1-426f-9247-bb680e5fe0c8").unwrap();
+
+  assert_eq!(id.as_bytes()[0], 0x67);
+  assert_eq!(OrderId::nil().as_bytes(), &[0; 16]);
+  assert_ne!(OrderId::new_v4(), OrderId::new_v4());
+  assert!(OrderId::parse_str("not a uuid").is_err());
+}